@@ -1,18 +1,30 @@
-use bevy::prelude::{Component, Handle, Image, Plugin, Query, Res};
+use bevy::prelude::{Component, Handle, Image, IntoSystemDescriptor, Plugin, Query, Res, SystemSet};
 use bevy::reflect::{GetTypeRegistration, Reflect};
 use bevy::time::Time;
 use bevy::{prelude::Resource, utils::HashMap};
+use bevy_rapier2d::prelude::Velocity;
 use lerp::num_traits::Zero;
 
 use crate::assets::SpriteEnum::*;
-use crate::assets::{GameAssets, SpriteEnum};
+use crate::assets::{AppState, GameAssets, SpriteEnum};
+use crate::schedule::GameSystems;
+
+/// Moving slower than this looks like standing still, unless a `Locomotion` says otherwise.
+pub const WALK_SPEED: f32 = 10.;
 
 pub struct AnimPlugin;
 
 impl Plugin for AnimPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
+        // Runs after the gameplay animation systems have picked what to play this frame.
         app.insert_resource(Animations::default())
-            .add_system(animator_sys);
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .label(GameSystems::Animation)
+                    .after(GameSystems::Interaction)
+                    .with_system(play_locomotion)
+            )
+            .add_system(animator_sys.after(GameSystems::Animation));
     }
 }
 
@@ -167,6 +179,47 @@ impl Animator {
     }
 }
 
+/// Something an animator can show: an animation, or a single frame.
+#[derive(Clone, Copy)]
+pub enum Clip {
+    Anim(AnimEnum),
+    Sprite(SpriteEnum),
+}
+
+/// Plays `walk` while the entity's `Velocity` is at least `walk_speed`, and `idle` otherwise.
+/// One-shot animations, like attacks, are left to finish first.
+#[derive(Component, Clone, Copy)]
+pub struct Locomotion {
+    pub idle: Clip,
+    pub walk: Clip,
+    pub walk_speed: f32,
+}
+
+impl Locomotion {
+    pub fn new(idle: Clip, walk: Clip) -> Self {
+        Self { idle, walk, walk_speed: WALK_SPEED }
+    }
+}
+
+pub fn play_locomotion(
+    mut query: Query<(&mut Animator, &Velocity, &Locomotion)>,
+    anims: Res<Animations>,
+) {
+    for (mut animator, velocity, locomotion) in query.iter_mut() {
+        let current = &animator.current_anim;
+        if current.one_shot && current.anim_enum != AnimEnum::StaticSprite && animator.playing {
+            continue;
+        }
+        let clip = if velocity.linvel.length() >= locomotion.walk_speed { locomotion.walk } else { locomotion.idle };
+        match clip {
+            Clip::Anim(anim) => animator.play_anim(anims.get(anim)),
+            // Restarting the same frame every frame would keep `playing` from ever running out.
+            Clip::Sprite(sprite) if animator.current_anim.sprites == [sprite] => {}
+            Clip::Sprite(sprite) => animator.play_sprite(sprite),
+        }
+    }
+}
+
 pub fn animator_sys(
     mut animators: Query<(&mut Animator, &mut Handle<Image>)>,
     assets: Res<GameAssets>,
@@ -174,7 +227,7 @@ pub fn animator_sys(
 ) {
     for (mut animator, mut handle) in animators.iter_mut() {
         if !animator.playing {
-            return;
+            continue;
         }
         if animator.time.is_zero() {
             *handle = assets.get(animator.current_anim.sprites[0]);
@@ -199,3 +252,33 @@ pub fn animator_sys(
         animator.time += time.delta_seconds();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use crate::init_systems::{LevelState, Mousey};
+    use crate::player::Trunk;
+    use crate::save::Character;
+    use crate::test_app::{mousey_freed, TestApp};
+    use super::{AnimEnum, Animator};
+
+    #[test]
+    fn only_the_character_that_moves_walks() {
+        let mut app = TestApp::new();
+        app.start_as(LevelState::HouseFront, Character::Mousey, (-50., -150.), mousey_freed());
+        let mousey = app.single::<With<Mousey>>();
+        let trunk = app.single::<With<Trunk>>();
+        let playing = |world: &World, entity| world.get::<Animator>(entity).unwrap().current_anim.anim_enum;
+
+        // Trunk sets off after Mousey on his own, while the player holds nothing down.
+        app.step_until(60, "Trunk walking", |world| {
+            world.get::<Animator>(trunk).unwrap().playing && playing(world, trunk) == AnimEnum::TrunkWalk
+        });
+        assert!(playing(&app.0.world, mousey) == AnimEnum::StaticSprite);
+
+        app.input().movement = Vec2::X;
+        app.step();
+        app.step();
+        assert!(playing(&app.0.world, mousey) == AnimEnum::MouseyWalk);
+    }
+}
@@ -1,24 +1,92 @@
-use bevy::asset::LoadState;
-use bevy::{prelude::*, utils::HashMap};
-use crate::assets::AudioEnum::MusicMainTheme;
+use std::path::Path;
+use bevy::asset::{AssetIo, LoadState};
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::reflect::{TypeInfo, Typed};
+use bevy::{prelude::*, utils::{HashMap, HashSet}};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use crate::init_systems::LevelState;
+use crate::dialogue::DialogueDef;
+use crate::init_systems::level::LevelDef;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub enum AppState {
     Loading,
+    LoadError,
     MainMenu,
     InGame,
+    /// Pushed on top of `InGame`, so leaving it resumes the game where it was.
+    Paused,
 }
 
 #[derive(Default, Resource, Clone)]
 pub struct GameAssets {
     pub sprites: HashMap<SpriteEnum, Handle<Image>>,
-    pub audio: HashMap<AudioEnum, Handle<AudioSource>>
+    pub audio: HashMap<AudioEnum, Handle<AudioSource>>,
+    pub named_sprites: HashMap<String, Handle<Image>>,
+    pub named_audio: HashMap<String, Handle<AudioSource>>,
+    pub fonts: HashMap<FontEnum, Handle<Font>>,
+    pub levels: HashMap<LevelState, Handle<LevelDef>>,
+    pub dialogues: HashMap<String, Handle<DialogueDef>>,
 }
 
 impl GameAssets {
     pub fn get(&self, sprite: SpriteEnum) -> Handle<Image> {
         self.sprites.get(&sprite).unwrap().clone()
     }
+
+    /// Looks a sprite up by its manifest key, including keys that have no `SpriteEnum` variant.
+    pub fn get_named(&self, key: &str) -> Option<Handle<Image>> {
+        self.named_sprites.get(key).cloned()
+    }
+
+    pub fn font(&self, font: FontEnum) -> Handle<Font> {
+        self.fonts.get(&font).unwrap().clone()
+    }
+
+    /// Every sprite, sound and font as an empty handle, and the levels parsed straight from disk,
+    /// for running gameplay systems without a renderer or an `AssetServer`.
+    #[cfg(test)]
+    pub fn stub(world: &mut World) -> Self {
+        let mut assets = GameAssets::default();
+        for name in variant_names::<SpriteEnum>() {
+            assets.sprites.insert(parse_key(name).unwrap(), Handle::default());
+        }
+        for name in variant_names::<AudioEnum>() {
+            assets.audio.insert(parse_key(name).unwrap(), Handle::default());
+        }
+        for name in variant_names::<FontEnum>() {
+            assets.fonts.insert(parse_key(name).unwrap(), Handle::default());
+        }
+
+        let manifest = AssetManifest::parse(MANIFEST).unwrap();
+        for entry in manifest.assets.iter() {
+            match entry.kind {
+                AssetKind::Sprite => {
+                    assets.named_sprites.insert(entry.key.clone(), Handle::default());
+                }
+                AssetKind::Audio => {
+                    assets.named_audio.insert(entry.key.clone(), Handle::default());
+                }
+                AssetKind::Font => {}
+                AssetKind::Level => {
+                    let level: LevelDef = read_data_file(&entry.path);
+                    let handle = world.resource_mut::<Assets<LevelDef>>().add(level);
+                    assets.levels.insert(parse_key(&entry.key).unwrap(), handle);
+                }
+                AssetKind::Dialogue => {
+                    let dialogue: DialogueDef = read_data_file(&entry.path);
+                    let handle = world.resource_mut::<Assets<DialogueDef>>().add(dialogue);
+                    assets.dialogues.insert(entry.key.clone(), handle);
+                }
+            }
+        }
+        assets
+    }
+
+    pub fn dialogue(&self, key: &str) -> Option<Handle<DialogueDef>> {
+        self.dialogues.get(key).cloned()
+    }
 }
 
 pub struct AssetLoaderPlugin;
@@ -28,6 +96,8 @@ impl Plugin for AssetLoaderPlugin {
         app.add_state(AppState::Loading)
             .insert_resource(GameAssets::default())
             .insert_resource(AssetsLoading::default())
+            .init_resource::<LoadFailurePolicy>()
+            .init_resource::<LoadingProgress>()
             .add_system_set(SystemSet::on_enter(AppState::Loading).with_system(load_assets))
             .add_system_set(
                 SystemSet::on_update(AppState::Loading).with_system(check_assets_loaded),
@@ -35,15 +105,58 @@ impl Plugin for AssetLoaderPlugin {
     }
 }
 
+/// What `check_assets_loaded` does when an asset fails to load.
+#[derive(Resource, Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub enum LoadFailurePolicy {
+    /// Any failure stops the game in `AppState::LoadError`.
+    Strict,
+    /// Sprites and audio marked `optional` in the manifest are swapped for a placeholder
+    /// texture or silence and loading carries on. Anything else still stops the game.
+    #[default]
+    SubstituteOptional,
+}
+
+pub struct LoadingAsset {
+    pub key: String,
+    pub path: String,
+    pub kind: AssetKind,
+    pub optional: bool,
+    pub handle: HandleUntyped,
+}
+
+#[derive(Default, Resource, Clone, Copy, Debug)]
+pub struct LoadingProgress {
+    pub loaded: usize,
+    pub total: usize,
+}
+
+impl LoadingProgress {
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            return 1.;
+        }
+        self.loaded as f32 / self.total as f32
+    }
+}
+
 #[derive(Default, Resource)]
-pub struct AssetsLoading(Vec<HandleUntyped>);
+pub struct AssetsLoading {
+    pub pending: Vec<LoadingAsset>,
+    /// Paths that failed and could not be substituted.
+    pub failed: Vec<String>,
+}
 
-#[derive(PartialEq, Eq, Hash, Copy, Clone, Reflect)]
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Reflect, Deserialize)]
 pub enum AudioEnum {
     MusicMainTheme
 }
 
-#[derive(PartialEq, Eq, Hash, Copy, Clone, Reflect)]
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Reflect, Deserialize)]
+pub enum FontEnum {
+    Ui,
+}
+
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Reflect, Deserialize)]
 pub enum SpriteEnum {
     TrunkIdle1,
     TrunkIdle2,
@@ -92,213 +205,308 @@ pub enum SpriteEnum {
     DebugCircle,
 }
 
+const MANIFEST: &str = include_str!("../assets/manifest.ron");
+/// Built into the executable, so the load error screen can still be read when a font is what failed.
+/// Kept apart from `assets/` so a missing or broken game font can't take the fallback with it.
+const FALLBACK_FONT: &[u8] = include_bytes!("fonts/DejaVuSansMono.ttf");
+
+#[derive(Deserialize, Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum AssetKind {
+    Sprite,
+    Audio,
+    Font,
+    Level,
+    Dialogue,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ManifestEntry {
+    pub key: String,
+    pub kind: AssetKind,
+    pub path: String,
+    /// Optional assets may be replaced by a placeholder if they fail to load, see `LoadFailurePolicy`.
+    #[serde(default)]
+    pub optional: bool,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct AssetManifest {
+    pub assets: Vec<ManifestEntry>,
+}
+
+/// Problems found in the manifest. None of these stop loading; they are logged so that
+/// a typo in `manifest.ron` shows up in the console instead of as a missing texture.
+#[derive(Default, Debug)]
+pub struct ManifestReport {
+    /// Keys with no matching enum variant. Sprites and audio still load, by name only.
+    pub unknown_keys: Vec<String>,
+    pub duplicate_keys: Vec<String>,
+    pub duplicate_paths: Vec<String>,
+    pub missing_files: Vec<String>,
+    /// Enum variants that have no manifest entry, which `GameAssets::get` and friends would panic on.
+    pub unmapped_variants: Vec<String>,
+}
+
+impl AssetManifest {
+    pub fn parse(ron_str: &str) -> Result<Self, ron::error::SpannedError> {
+        ron::from_str(ron_str)
+    }
+
+    pub fn validate(&self, asset_io: &dyn AssetIo) -> ManifestReport {
+        let mut report = ManifestReport::default();
+        let mut keys = HashSet::new();
+        let mut paths = HashSet::new();
+
+        for entry in self.assets.iter() {
+            let known = match entry.kind {
+                AssetKind::Sprite => parse_key::<SpriteEnum>(&entry.key).is_some(),
+                AssetKind::Audio => parse_key::<AudioEnum>(&entry.key).is_some(),
+                AssetKind::Font => parse_key::<FontEnum>(&entry.key).is_some(),
+                AssetKind::Level => parse_key::<LevelState>(&entry.key).is_some(),
+                // Dialogues are only ever looked up by name.
+                AssetKind::Dialogue => true,
+            };
+            if !known {
+                report.unknown_keys.push(entry.key.clone());
+            }
+            if !keys.insert((entry.kind, entry.key.as_str())) {
+                report.duplicate_keys.push(entry.key.clone());
+            }
+            if !paths.insert(entry.path.as_str()) {
+                report.duplicate_paths.push(entry.path.clone());
+            }
+            if !asset_io.is_file(Path::new(&entry.path)) {
+                report.missing_files.push(entry.path.clone());
+            }
+        }
+
+        for (kind, names) in [
+            (AssetKind::Sprite, variant_names::<SpriteEnum>()),
+            (AssetKind::Audio, variant_names::<AudioEnum>()),
+            (AssetKind::Font, variant_names::<FontEnum>()),
+            (AssetKind::Level, variant_names::<LevelState>()),
+        ] {
+            for &name in names {
+                if !keys.contains(&(kind, name)) {
+                    report.unmapped_variants.push(name.to_string());
+                }
+            }
+        }
+
+        report
+    }
+}
+
+impl ManifestReport {
+    pub fn report(&self) {
+        for key in self.unknown_keys.iter() {
+            info!("asset manifest: `{key}` has no enum variant, it can only be looked up by name");
+        }
+        for key in self.duplicate_keys.iter() {
+            warn!("asset manifest: key `{key}` is listed more than once, the last entry wins");
+        }
+        for path in self.duplicate_paths.iter() {
+            warn!("asset manifest: `{path}` is listed under more than one key");
+        }
+        for path in self.missing_files.iter() {
+            error!("asset manifest: `{path}` does not exist under assets/");
+        }
+        for name in self.unmapped_variants.iter() {
+            error!("asset manifest: `{name}` has no entry");
+        }
+    }
+}
+
+fn parse_key<T: DeserializeOwned>(key: &str) -> Option<T> {
+    ron::from_str(key).ok()
+}
+
+#[cfg(test)]
+fn read_data_file<T: DeserializeOwned>(path: &str) -> T {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets").join(path);
+    let text = std::fs::read_to_string(&path).unwrap();
+    ron::from_str(&text).unwrap_or_else(|err| panic!("{path:?} is not valid: {err}"))
+}
+
+fn variant_names<T: Typed>() -> &'static [&'static str] {
+    match T::type_info() {
+        TypeInfo::Enum(info) => info.variant_names(),
+        _ => &[],
+    }
+}
+
 pub fn load_assets(
     mut assets: ResMut<GameAssets>,
     mut loading: ResMut<AssetsLoading>,
+    mut progress: ResMut<LoadingProgress>,
     asset_server: Res<AssetServer>,
 ) {
-    assets.sprites.insert(
-        SpriteEnum::HouseFront,
-        asset_server.load("sprites/house_front.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::HouseFrontBackground,
-        asset_server.load("sprites/house_front_background.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::HouseFrontHouse,
-        asset_server.load("sprites/house_front_house.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::HouseFrontTree1,
-        asset_server.load("sprites/house_front_tree_1.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::HouseFrontTree2,
-        asset_server.load("sprites/house_front_tree_2.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::HouseInside,
-        asset_server.load("sprites/house_inside.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::LadyIdle,
-        asset_server.load("sprites/lady_idle.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::MouseyWalk1,
-        asset_server.load("sprites/mousey/mouseywalk1.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::MouseyWalk2,
-        asset_server.load("sprites/mousey/mouseywalk2.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::MouseyWalk3,
-        asset_server.load("sprites/mousey/mouseywalk3.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::MouseyWalk4,
-        asset_server.load("sprites/mousey/mouseywalk4.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::MouseyWalk5,
-        asset_server.load("sprites/mousey/mouseywalk5.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::MouseyIdle1,
-        asset_server.load("sprites/mousey/mousey_idle1.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::MouseyIdle2,
-        asset_server.load("sprites/mousey/mousey_idle2.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::MouseyIdle3,
-        asset_server.load("sprites/mousey/mousey_idle3.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::Bug1,
-        asset_server.load("sprites/bug/buggyboo1.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::Bug2,
-        asset_server.load("sprites/bug/buggyboo2.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::Bug3,
-        asset_server.load("sprites/bug/buggyboo3.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::Bug4,
-        asset_server.load("sprites/bug/buggyboo4.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::TrashCan,
-        asset_server.load("sprites/trash_can.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::TrunkWalk1,
-        asset_server.load("sprites/trunk/jr_walk1.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::TrunkWalk2,
-        asset_server.load("sprites/trunk/jr_walk2.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::TrunkWalk3,
-        asset_server.load("sprites/trunk/jr_walk3.png"),
-    );
-    assets
-        .sprites
-        .insert(SpriteEnum::Shadow, asset_server.load("sprites/shadow.png"));
-    assets.sprites.insert(
-        SpriteEnum::TrunkIdle1,
-        asset_server.load("sprites/trunk/jr_idle1.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::TrunkIdle2,
-        asset_server.load("sprites/trunk/jr_idle2.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::TrunkIdle3,
-        asset_server.load("sprites/trunk/jr_idle3.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::RoomBackground,
-        asset_server.load("sprites/room_background.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::RoomBackground2,
-        asset_server.load("sprites/room_background2.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::RoomFurniture1,
-        asset_server.load("sprites/room_furniture1.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::RoomFurniture2,
-        asset_server.load("sprites/room_furniture2.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::RoomFurniture3,
-        asset_server.load("sprites/room_furniture3.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::RoomSlidingDoor,
-        asset_server.load("sprites/room_slidingdoor.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::RoomDoor,
-        asset_server.load("sprites/room_door.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::RoomBirdDoor,
-        asset_server.load("sprites/Room_birddoor.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::GirlIdle1,
-        asset_server.load("sprites/girl_idle1.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::GirlIdle2,
-        asset_server.load("sprites/girl_idle2.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::GirlIdle3,
-        asset_server.load("sprites/girl_idle3.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::TrunkAttack1,
-        asset_server.load("sprites/jr_standardattack1.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::TrunkAttack2,
-        asset_server.load("sprites/jr_standardattack2.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::TrunkAttack3,
-        asset_server.load("sprites/jr_standardattack3.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::TrunkAttack4,
-        asset_server.load("sprites/jr_standardattack4.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::TrunkAttack5,
-        asset_server.load("sprites/jr_standardattack5.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::TrunkAttack6,
-        asset_server.load("sprites/jr_standardattack6.png"),
-    );
-    assets.sprites.insert(
-        SpriteEnum::DebugCircle,
-        asset_server.load("sprites/debug_circle.png"),
-    );
-
-    assets.audio.insert(
-        MusicMainTheme,
-        asset_server.load("audio/tree_game_theme.wav")
-    );
-
-    for (_, asset) in assets.sprites.iter() {
-        loading.0.push(asset.clone_untyped());
+    let manifest = AssetManifest::parse(MANIFEST).expect("assets/manifest.ron is not a valid manifest");
+    manifest.validate(asset_server.asset_io()).report();
+
+    for entry in manifest.assets.iter() {
+        let handle = match entry.kind {
+            AssetKind::Sprite => {
+                let handle: Handle<Image> = asset_server.load(entry.path.as_str());
+                if let Some(sprite) = parse_key::<SpriteEnum>(&entry.key) {
+                    assets.sprites.insert(sprite, handle.clone());
+                }
+                assets.named_sprites.insert(entry.key.clone(), handle.clone());
+                handle.clone_untyped()
+            }
+            AssetKind::Audio => {
+                let handle: Handle<AudioSource> = asset_server.load(entry.path.as_str());
+                if let Some(audio) = parse_key::<AudioEnum>(&entry.key) {
+                    assets.audio.insert(audio, handle.clone());
+                }
+                assets.named_audio.insert(entry.key.clone(), handle.clone());
+                handle.clone_untyped()
+            }
+            AssetKind::Font => {
+                let handle: Handle<Font> = asset_server.load(entry.path.as_str());
+                if let Some(font) = parse_key::<FontEnum>(&entry.key) {
+                    assets.fonts.insert(font, handle.clone());
+                }
+                handle.clone_untyped()
+            }
+            AssetKind::Level => {
+                let handle: Handle<LevelDef> = asset_server.load(entry.path.as_str());
+                if let Some(level) = parse_key::<LevelState>(&entry.key) {
+                    assets.levels.insert(level, handle.clone());
+                }
+                handle.clone_untyped()
+            }
+            AssetKind::Dialogue => {
+                let handle: Handle<DialogueDef> = asset_server.load(entry.path.as_str());
+                assets.dialogues.insert(entry.key.clone(), handle.clone());
+                handle.clone_untyped()
+            }
+        };
+
+        loading.pending.push(LoadingAsset {
+            key: entry.key.clone(),
+            path: entry.path.clone(),
+            kind: entry.kind,
+            optional: entry.optional,
+            handle,
+        });
     }
+
+    *progress = LoadingProgress { loaded: 0, total: loading.pending.len() };
 }
 
 pub fn check_assets_loaded(
     mut commands: Commands,
     mut state: ResMut<State<AppState>>,
     server: Res<AssetServer>,
-    loading: Res<AssetsLoading>,
+    mut loading: ResMut<AssetsLoading>,
+    mut progress: ResMut<LoadingProgress>,
+    mut assets: ResMut<GameAssets>,
+    mut images: ResMut<Assets<Image>>,
+    mut audio: ResMut<Assets<AudioSource>>,
+    mut fonts: ResMut<Assets<Font>>,
+    policy: Res<LoadFailurePolicy>,
 ) {
-    match server.get_group_load_state(loading.0.iter().map(|handle| handle.id)) {
-        LoadState::Failed => {}
-        LoadState::Loaded => {
-            commands.remove_resource::<AssetsLoading>();
-            state.set(AppState::InGame).unwrap();
+    let loading = &mut *loading;
+    loading.pending.retain(|asset| {
+        if server.get_load_state(asset.handle.id) != LoadState::Failed {
+            return true;
+        }
+        let substitute = asset.optional && *policy == LoadFailurePolicy::SubstituteOptional;
+        if substitute && substitute_placeholder(asset, &mut assets, &mut images, &mut audio) {
+            warn!("failed to load `{}`, using a placeholder instead", asset.path);
+        } else {
+            error!("failed to load `{}`", asset.path);
+            loading.failed.push(asset.path.clone());
+            if asset.kind == AssetKind::Font {
+                use_fallback_font(asset, &mut assets, &mut fonts);
+            }
+        }
+        false
+    });
+
+    let still_loading = loading.pending.iter()
+        .filter(|asset| server.get_load_state(asset.handle.id) != LoadState::Loaded)
+        .count();
+    progress.loaded = progress.total - still_loading;
+
+    // Failures are only acted on once everything has loaded or failed, so the error screen lists
+    // all of them and every failed font has its fallback.
+    if still_loading > 0 {
+        return;
+    }
+    if loading.failed.is_empty() {
+        commands.remove_resource::<AssetsLoading>();
+        state.set(AppState::MainMenu).unwrap();
+    } else {
+        state.set(AppState::LoadError).unwrap();
+    }
+}
+
+/// Swaps a failed asset for a generated stand-in. Returns false if there is no stand-in for its kind.
+fn substitute_placeholder(
+    asset: &LoadingAsset,
+    assets: &mut GameAssets,
+    images: &mut Assets<Image>,
+    audio: &mut Assets<AudioSource>,
+) -> bool {
+    match asset.kind {
+        AssetKind::Sprite => {
+            let handle = images.add(Image::new_fill(
+                Extent3d { width: 64, height: 64, depth_or_array_layers: 1 },
+                TextureDimension::D2,
+                &[255, 0, 255, 255],
+                TextureFormat::Rgba8UnormSrgb,
+            ));
+            if let Some(sprite) = parse_key::<SpriteEnum>(&asset.key) {
+                assets.sprites.insert(sprite, handle.clone());
+            }
+            assets.named_sprites.insert(asset.key.clone(), handle);
+            true
+        }
+        AssetKind::Audio => {
+            let handle = audio.add(AudioSource { bytes: silent_wav().into() });
+            if let Some(track) = parse_key::<AudioEnum>(&asset.key) {
+                assets.audio.insert(track, handle.clone());
+            }
+            assets.named_audio.insert(asset.key.clone(), handle);
+            true
+        }
+        AssetKind::Font | AssetKind::Level | AssetKind::Dialogue => false,
+    }
+}
+
+/// Points a failed font's key at the built-in font. Fonts are never substituted during play, since
+/// the game would stop in `AppState::LoadError` anyway, but the error screen needs something to
+/// write the failed paths with.
+fn use_fallback_font(asset: &LoadingAsset, assets: &mut GameAssets, fonts: &mut Assets<Font>) {
+    let Some(font) = parse_key::<FontEnum>(&asset.key) else { return; };
+    match Font::try_from_bytes(FALLBACK_FONT.to_vec()) {
+        Ok(fallback) => {
+            assets.fonts.insert(font, fonts.add(fallback));
         }
-        _ => {}
+        Err(err) => error!("the built-in fallback font is unreadable: {err:?}"),
     }
 }
+
+/// A tenth of a second of 16-bit mono PCM silence.
+fn silent_wav() -> Vec<u8> {
+    const SAMPLE_RATE: u32 = 44100;
+    const DATA_LEN: u32 = SAMPLE_RATE / 10 * 2;
+
+    let mut wav = Vec::with_capacity(44 + DATA_LEN as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + DATA_LEN).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes()); // byte rate
+    wav.extend_from_slice(&2u16.to_le_bytes()); // block align
+    wav.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&DATA_LEN.to_le_bytes());
+    wav.resize(44 + DATA_LEN as usize, 0);
+    wav
+}
@@ -1,16 +1,29 @@
 use crate::{
-    assets::{GameAssets, SpriteEnum},
-    player::Player,
+    assets::{AppState, GameAssets, SpriteEnum},
+    schedule::GameSystems,
 };
 use bevy::{
     prelude::{
-        default, Camera, Commands, Component, GlobalTransform, OrthographicProjection, Query, Res,
-        Resource, Time, Transform, Vec2, Vec3, With, Without,
+        default, App, Camera, Commands, Component, GlobalTransform, OrthographicProjection, Plugin,
+        Query, Res, Resource, SystemSet, Time, Transform, Vec2, Vec3, With, Without,
     },
     sprite::SpriteBundle,
 };
 use lerp::Lerp;
 
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(AppState::InGame)
+                .label(GameSystems::Camera)
+                .after(GameSystems::Animation)
+                .with_system(camera_follow)
+        );
+    }
+}
+
 #[derive(Resource, Component)]
 pub struct MainCamera;
 
@@ -22,12 +35,17 @@ pub struct CameraBounds(pub f32, pub f32);
 #[derive(Component)]
 pub struct LockedCamera;
 
+/// The character the camera follows. Usually the player, but it stays on them through cutscenes
+/// that take `Player` away.
+#[derive(Component)]
+pub struct CameraTarget;
+
 pub fn camera_follow(
     mut camera_q: Query<
         (&mut Transform, &Camera, Option<&CameraBounds>),
         (With<MainCamera>, Without<LockedCamera>),
     >,
-    player_q: Query<&Transform, (With<Player>, Without<MainCamera>)>,
+    player_q: Query<&Transform, (With<CameraTarget>, Without<MainCamera>)>,
     time: Res<Time>,
 ) {
     let Ok((mut cam_trans, cam, bounds)) = camera_q.get_single_mut()
@@ -0,0 +1,149 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::{QueryFilter, RapierContext};
+use crate::assets::AppState;
+use crate::init_systems::{Mousey, YOffset};
+use crate::keyboard_input::PlayerInput;
+use crate::interaction::{hover_interactables, HoveredInteractable, in_reach, Interactable};
+use crate::player::{Player, TRUNK_MAX_SPEED};
+use crate::schedule::GameSystems;
+use crate::settings::GameSettings;
+use crate::story::StoryFlags;
+
+pub const ARRIVE_DISTANCE: f32 = 4.;
+/// How far to stay from whatever a path goes around.
+const PATH_CLEARANCE: f32 = 15.;
+/// Sideways distances tried, in order, when looking for a way around an obstacle.
+const DETOUR_OFFSETS: [f32; 5] = [40., 80., 120., 180., 250.];
+/// Giving up after this long without getting closer means something is in the way.
+const STUCK_TIME: f32 = 0.25;
+
+pub struct ClickToMovePlugin;
+
+impl Plugin for ClickToMovePlugin {
+    fn build(&self, app: &mut App) {
+        // Steering is more input, worked out from the click, so it happens before anything moves.
+        app.add_system_set(
+            SystemSet::on_update(AppState::InGame)
+                .after(GameSystems::Input)
+                .before(GameSystems::Movement)
+                .with_system(click_to_move.after(hover_interactables))
+                .with_system(follow_move_target.after(click_to_move))
+        );
+    }
+}
+
+/// Waypoints for the player's feet to walk through, ending at the clicked point.
+#[derive(Component)]
+pub struct MoveTarget {
+    path: Vec<Vec2>,
+    last_distance: f32,
+    stuck_for: f32,
+}
+
+impl MoveTarget {
+    fn new(path: Vec<Vec2>) -> Self {
+        Self { path, last_distance: f32::INFINITY, stuck_for: 0. }
+    }
+}
+
+// Characters are placed by their middle; `YOffset` is where their feet are relative to it.
+pub fn feet(trans: &Transform, y_off: Option<&YOffset>) -> Vec2 {
+    trans.translation.truncate() + Vec2::Y * y_off.map_or(0., |y_off| y_off.0)
+}
+
+fn click_to_move(
+    mut commands: Commands,
+    settings: Res<GameSettings>,
+    input: Res<PlayerInput>,
+    hovered: Res<HoveredInteractable>,
+    flags: Res<StoryFlags>,
+    rapier: Res<RapierContext>,
+    players: Query<(Entity, &Transform, &GlobalTransform, Option<&YOffset>, Option<&Mousey>), With<Player>>,
+    interactables: Query<(&Interactable, &GlobalTransform)>,
+) {
+    if !settings.click_to_move || !input.just_clicked {
+        return;
+    }
+    let clicked = hovered.0.and_then(|entity| interactables.get(entity).ok());
+    for (entity, trans, global, y_off, mousey) in players.iter() {
+        // Clicking an interactable in reach uses it instead, see `click_to_interact`.
+        if clicked.map_or(false, |(interactable, at)| in_reach(interactable, at, global, mousey, &flags)) {
+            continue;
+        }
+        let filter = QueryFilter::default().exclude_sensors().exclude_rigid_body(entity);
+        let path = plan_path(&rapier, filter, feet(trans, y_off), input.mouse_pos);
+        commands.entity(entity).insert(MoveTarget::new(path));
+    }
+}
+
+/// Distance along the straight line from `from` to `to` before hitting a collider, if one is in the way.
+fn obstacle_on(rapier: &RapierContext, filter: QueryFilter, from: Vec2, to: Vec2) -> Option<f32> {
+    let length = from.distance(to);
+    if length < f32::EPSILON {
+        return None;
+    }
+    rapier.cast_ray(from, (to - from) / length, length, true, filter).map(|(_, toi)| toi)
+}
+
+/// A straight line if nothing is in the way, otherwise a single detour around the first obstacle.
+/// If there's no way around, walks up to the obstacle.
+pub fn plan_path(rapier: &RapierContext, filter: QueryFilter, from: Vec2, to: Vec2) -> Vec<Vec2> {
+    let Some(toi) = obstacle_on(rapier, filter, from, to) else { return vec![to]; };
+    let dir = (to - from).normalize();
+    let hit = from + dir * toi;
+
+    for offset in DETOUR_OFFSETS {
+        for side in [dir.perp(), -dir.perp()] {
+            let via = hit - dir * PATH_CLEARANCE + side * offset;
+            if obstacle_on(rapier, filter, from, via).is_none() && obstacle_on(rapier, filter, via, to).is_none() {
+                return vec![via, to];
+            }
+        }
+    }
+    vec![from + dir * (toi - PATH_CLEARANCE).max(0.)]
+}
+
+// Steers by writing `PlayerInput.movement`, so walking speed, acceleration and animations all
+// work the same as with the keyboard.
+fn follow_move_target(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut input: ResMut<PlayerInput>,
+    mut players: Query<(Entity, &Transform, Option<&YOffset>, &mut MoveTarget), With<Player>>,
+) {
+    for (entity, trans, y_off, mut target) in players.iter_mut() {
+        // Moving with the keyboard or a gamepad cancels the walk.
+        if input.movement != Vec2::ZERO {
+            commands.entity(entity).remove::<MoveTarget>();
+            continue;
+        }
+        let Some(&next) = target.path.first()
+            else {
+                commands.entity(entity).remove::<MoveTarget>();
+                continue;
+            };
+
+        let to_next = next - feet(trans, y_off);
+        let distance = to_next.length();
+        if distance < ARRIVE_DISTANCE {
+            target.path.remove(0);
+            target.last_distance = f32::INFINITY;
+            target.stuck_for = 0.;
+            continue;
+        }
+
+        let progress = target.last_distance - distance;
+        if progress < TRUNK_MAX_SPEED * 0.1 * time.delta_seconds() {
+            target.stuck_for += time.delta_seconds();
+        } else {
+            target.stuck_for = 0.;
+        }
+        target.last_distance = distance;
+        if target.stuck_for > STUCK_TIME {
+            commands.entity(entity).remove::<MoveTarget>();
+            continue;
+        }
+
+        input.movement = to_next / distance;
+    }
+}
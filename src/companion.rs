@@ -0,0 +1,158 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::{QueryFilter, RapierContext, Velocity};
+use crate::assets::AppState;
+use crate::click_to_move::{ARRIVE_DISTANCE, feet, plan_path};
+use crate::init_systems::YOffset;
+use crate::player::{accelerate, move_player, Player};
+use crate::roster::{ControllableCharacter, unlocked};
+use crate::schedule::GameSystems;
+use crate::story::StoryFlags;
+
+/// A companion further than this from the player sets off after them...
+pub const START_DISTANCE: f32 = 120.;
+/// ...and stops again once this close, so they trail behind instead of bumping into the player.
+const STOP_DISTANCE: f32 = 70.;
+/// ...unless the player is already further than this, in which case the companion was left behind
+/// on purpose and stays where they are.
+const NOTICE_DISTANCE: f32 = 500.;
+/// The player keeps moving, so the path to them is worked out again this often.
+const REPLAN_TIME: f32 = 0.5;
+
+/// Has the unlocked characters the player isn't controlling follow the one they are.
+pub struct CompanionPlugin;
+
+impl Plugin for CompanionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(AppState::InGame)
+                .label(GameSystems::Movement)
+                .after(GameSystems::Input)
+                .with_system(follow_player.after(move_player))
+        );
+    }
+}
+
+/// Follow state for a `ControllableCharacter`, used while someone else is the player.
+#[derive(Component, Default)]
+pub struct Companion {
+    following: bool,
+    path: Vec<Vec2>,
+    replan_in: f32,
+}
+
+impl Companion {
+    pub fn is_following(&self) -> bool {
+        self.following
+    }
+
+    /// Drops the current path, for when the companion has been moved somewhere else.
+    pub fn replan(&mut self) {
+        self.path.clear();
+    }
+}
+
+fn follow_player(
+    time: Res<Time>,
+    rapier: Res<RapierContext>,
+    flags: Res<StoryFlags>,
+    players: Query<(Entity, &Transform, Option<&YOffset>), With<Player>>,
+    parents: Query<&Parent>,
+    mut companions: Query<
+        (Entity, &ControllableCharacter, &Transform, Option<&YOffset>, &mut Velocity, &mut Companion),
+        Without<Player>,
+    >,
+) {
+    // Nobody to follow while the player is busy with a door or a cutscene.
+    let Ok((player, player_trans, player_y_off)) = players.get_single() else { return; };
+    let goal = feet(player_trans, player_y_off);
+    // The player is what's being walked to, not something to walk around.
+    let not_player = |collider: Entity| {
+        collider != player && parents.get(collider).map_or(true, |parent| parent.get() != player)
+    };
+
+    for (entity, character, trans, y_off, mut velocity, mut companion) in companions.iter_mut() {
+        if !unlocked(character.0, &flags) {
+            continue;
+        }
+        let pos = feet(trans, y_off);
+        let distance = pos.distance(goal);
+        if distance > START_DISTANCE && distance < NOTICE_DISTANCE {
+            companion.following = true;
+        } else if distance < STOP_DISTANCE {
+            companion.following = false;
+            companion.path.clear();
+        }
+        if !companion.following {
+            continue;
+        }
+
+        companion.replan_in -= time.delta_seconds();
+        if companion.replan_in <= 0. || companion.path.is_empty() {
+            let filter = QueryFilter::default()
+                .exclude_sensors()
+                .exclude_rigid_body(entity)
+                .predicate(&not_player);
+            companion.path = plan_path(&rapier, filter, pos, goal);
+            companion.replan_in = REPLAN_TIME;
+        }
+        while companion.path.first().map_or(false, |next| next.distance(pos) < ARRIVE_DISTANCE) {
+            companion.path.remove(0);
+        }
+        let Some(&next) = companion.path.first() else { continue; };
+        accelerate(&mut velocity, (next - pos).normalize_or_zero(), time.delta_seconds());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use crate::click_to_move::feet;
+    use crate::init_systems::{LevelState, Mousey, YOffset};
+    use crate::init_systems::level::LevelEntity;
+    use crate::player::Trunk;
+    use crate::save::Character;
+    use crate::test_app::{mousey_freed, saved_as, TestApp};
+    use super::START_DISTANCE;
+
+    fn feet_of(world: &World, entity: Entity) -> Vec2 {
+        feet(world.get::<Transform>(entity).unwrap(), world.get::<YOffset>(entity))
+    }
+
+    #[test]
+    fn trunk_catches_up_with_mousey() {
+        let mut app = TestApp::new();
+        // Trunk starts on his spawn point, a long way left of Mousey.
+        app.start_as(LevelState::HouseFront, Character::Mousey, (-50., -150.), mousey_freed());
+        let mousey = app.single::<With<Mousey>>();
+        let trunk = app.single::<With<Trunk>>();
+
+        app.step_until(300, "Trunk catching up", |world| feet_of(world, mousey).distance(feet_of(world, trunk)) < START_DISTANCE);
+    }
+
+    #[test]
+    fn trunk_follows_mousey_through_the_door() {
+        let mut app = TestApp::new();
+        let mut save = saved_as(LevelState::HouseFront, Character::Mousey, (360., -140.), mousey_freed());
+        // Waiting next to Mousey, close enough not to need to walk.
+        save.0.places.set(Character::Trunk, LevelState::HouseFront, Vec2::new(320., -80.));
+        app.start_game(Some(save));
+        let mousey = app.single::<With<Mousey>>();
+        let trunk = app.single::<With<Trunk>>();
+
+        app.go_through_door(mousey, LevelState::HouseInside);
+        assert_eq!(app.single::<With<Trunk>>(), trunk);
+        assert_eq!(app.0.world.get::<LevelEntity>(trunk).unwrap().0, LevelState::HouseInside);
+        assert!(feet_of(&app.0.world, mousey).distance(feet_of(&app.0.world, trunk)) < START_DISTANCE);
+    }
+
+    #[test]
+    fn trunk_left_across_the_yard_stays_behind() {
+        let mut app = TestApp::new();
+        // Trunk is on his spawn point, on the far side of the yard from the front door.
+        app.start_as(LevelState::HouseFront, Character::Mousey, (360., -140.), mousey_freed());
+        let mousey = app.single::<With<Mousey>>();
+
+        app.go_through_door(mousey, LevelState::HouseInside);
+        assert_eq!(app.0.world.query_filtered::<Entity, With<Trunk>>().iter(&app.0.world).count(), 0);
+    }
+}
@@ -0,0 +1,485 @@
+use std::collections::HashMap;
+use bevy::asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::ui::FocusPolicy;
+use serde::Deserialize;
+use crate::assets::{AppState, FontEnum, GameAssets, SpriteEnum};
+use crate::click_to_move::MoveTarget;
+use crate::interaction::{dispatch_interactions, hover_interactables, Talk};
+use crate::keyboard_input::PlayerInput;
+use crate::schedule::GameSystems;
+use crate::story::{StoryCondition, StoryCounter, StoryFlag, StoryFlags};
+
+const CHARS_PER_SECOND: f32 = 40.;
+const BOX_COLOR: Color = Color::rgba(0., 0., 0., 0.8);
+const SELECTED_COLOR: Color = Color::rgb(1., 1., 0.6);
+const PORTRAIT_SIZE: f32 = 128.;
+
+pub struct DialoguePlugin;
+
+impl Plugin for DialoguePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<DialogueDef>()
+            .init_asset_loader::<DialogueLoader>()
+            // Takes the input before anything else in the game sees it.
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .after(GameSystems::Input)
+                    .before(GameSystems::Movement)
+                    .with_system(dialogue_input.before(hover_interactables))
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .label(GameSystems::Interaction)
+                    .after(GameSystems::Movement)
+                    .with_system(start_dialogue.after(dispatch_interactions))
+            )
+            // UI, so not part of any gameplay phase, but drawn once this frame's conversation has started.
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .after(GameSystems::Interaction)
+                    .with_system(draw_dialogue)
+            )
+            // Out of the way of the pause menu, and back where it was on resuming.
+            .add_system_set(
+                SystemSet::on_enter(AppState::Paused)
+                    .with_system(show_dialogue_box(false))
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::Paused)
+                    .with_system(show_dialogue_box(true))
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::InGame)
+                    .with_system(end_dialogue)
+            );
+    }
+}
+
+/// A conversation, loaded from an `assets/dialogue/*.dialogue.ron` file listed in the asset manifest.
+#[derive(Deserialize, TypeUuid, Clone, Debug)]
+#[uuid = "3b0f3f64-5a0e-4d55-9e0b-4c1f2a7d9e21"]
+pub struct DialogueDef {
+    /// The conversation starts at the first entry whose conditions hold.
+    pub entries: Vec<DialogueEntry>,
+    pub nodes: HashMap<String, DialogueNode>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct DialogueEntry {
+    pub node: String,
+    #[serde(default)]
+    pub conditions: Vec<StoryCondition>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct DialogueNode {
+    pub lines: Vec<DialogueLine>,
+    /// Offered after the last line. Choices whose conditions don't hold are left out.
+    #[serde(default)]
+    pub choices: Vec<DialogueChoice>,
+    /// Where to go after the last line if there are no choices. The conversation ends if there's nowhere.
+    #[serde(default)]
+    pub next: Option<String>,
+    /// Set as soon as the node is reached.
+    #[serde(default)]
+    pub set_flags: Vec<StoryFlag>,
+    /// Each of these goes up by one when the node is reached.
+    #[serde(default)]
+    pub count: Vec<StoryCounter>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct DialogueLine {
+    pub speaker: String,
+    #[serde(default)]
+    pub portrait: Option<SpriteEnum>,
+    pub text: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct DialogueChoice {
+    pub text: String,
+    #[serde(default)]
+    pub next: Option<String>,
+    #[serde(default)]
+    pub conditions: Vec<StoryCondition>,
+}
+
+#[derive(Default)]
+pub struct DialogueLoader;
+
+impl AssetLoader for DialogueLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let dialogue: DialogueDef = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(dialogue));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["dialogue.ron"]
+    }
+}
+
+/// The conversation in progress. The player can't move while it exists.
+#[derive(Resource)]
+pub struct ActiveDialogue {
+    dialogue: DialogueDef,
+    node: String,
+    line: usize,
+    /// How many characters of the current line have been typed out.
+    typed: f32,
+    choice: usize,
+    /// Vertical input last frame, so holding up or down only moves the selection once.
+    last_vertical: i32,
+}
+
+impl ActiveDialogue {
+    fn node(&self) -> Option<&DialogueNode> {
+        self.dialogue.nodes.get(&self.node)
+    }
+
+    fn current_line(&self) -> Option<&DialogueLine> {
+        self.node()?.lines.get(self.line)
+    }
+
+    fn line_typed(&self) -> bool {
+        self.current_line().map_or(true, |line| self.typed as usize >= line.text.chars().count())
+    }
+
+    fn on_last_line(&self) -> bool {
+        self.node().map_or(true, |node| self.line + 1 >= node.lines.len())
+    }
+
+    /// The choices to pick from, once the last line has been typed out.
+    fn choices(&self, flags: &StoryFlags) -> Vec<&DialogueChoice> {
+        match self.node() {
+            Some(node) if self.on_last_line() && self.line_typed() => node.choices.iter()
+                .filter(|choice| flags.check(&choice.conditions))
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Moves to `node`, returning false if the dialogue has no such node.
+    fn enter(&mut self, node: &str, flags: &mut StoryFlags) -> bool {
+        let Some(def) = self.dialogue.nodes.get(node)
+            else {
+                warn!("dialogue has no node `{node}`");
+                return false;
+            };
+        for &flag in def.set_flags.iter() {
+            flags.set(flag);
+        }
+        for &counter in def.count.iter() {
+            flags.increment(counter);
+        }
+        self.node = node.to_string();
+        self.line = 0;
+        self.typed = 0.;
+        self.choice = 0;
+        true
+    }
+}
+
+#[derive(Component)]
+pub struct DialogueBox;
+
+#[derive(Component)]
+pub struct DialoguePortrait;
+
+#[derive(Component)]
+pub struct DialogueSpeaker;
+
+#[derive(Component)]
+pub struct DialogueText;
+
+#[derive(Component)]
+pub struct DialogueChoices;
+
+fn start_dialogue(
+    mut commands: Commands,
+    mut talks: EventReader<Talk>,
+    active: Option<Res<ActiveDialogue>>,
+    assets: Res<GameAssets>,
+    dialogues: Res<Assets<DialogueDef>>,
+    mut flags: ResMut<StoryFlags>,
+    walking: Query<Entity, With<MoveTarget>>,
+) {
+    for Talk { dialogue, .. } in talks.iter() {
+        if active.is_some() {
+            continue;
+        }
+        let Some(def) = assets.dialogue(dialogue).and_then(|handle| dialogues.get(&handle))
+            else {
+                error!("no dialogue data for `{dialogue}`");
+                continue;
+            };
+        let Some(entry) = def.entries.iter().find(|entry| flags.check(&entry.conditions))
+            else { continue; };
+
+        let mut conversation = ActiveDialogue {
+            dialogue: def.clone(),
+            node: String::new(),
+            line: 0,
+            typed: 0.,
+            choice: 0,
+            last_vertical: 0,
+        };
+        if conversation.enter(&entry.node, &mut flags) {
+            commands.insert_resource(conversation);
+            // Don't carry on walking to wherever was clicked before.
+            for entity in walking.iter() {
+                commands.entity(entity).remove::<MoveTarget>();
+            }
+        }
+        return;
+    }
+}
+
+// Interact (or a click) finishes typing the line, then moves on to the next line, or picks a choice.
+fn dialogue_input(
+    mut commands: Commands,
+    dialogue: Option<ResMut<ActiveDialogue>>,
+    mut input: ResMut<PlayerInput>,
+    mut flags: ResMut<StoryFlags>,
+    time: Res<Time>,
+) {
+    let Some(mut dialogue) = dialogue else { return; };
+    let pressed = input.just_interacted || input.just_clicked;
+    let vertical = if input.movement.y > 0.5 { -1 } else if input.movement.y < -0.5 { 1 } else { 0 };
+    // Nothing else in the game gets to act on the input while talking.
+    let mouse_pos = input.mouse_pos;
+    *input = PlayerInput { mouse_pos, ..default() };
+
+    dialogue.typed += time.delta_seconds() * CHARS_PER_SECOND;
+
+    let choice_count = dialogue.choices(&flags).len();
+    if choice_count > 0 && vertical != 0 && dialogue.last_vertical == 0 {
+        dialogue.choice = (dialogue.choice as i32 + vertical).rem_euclid(choice_count as i32) as usize;
+    }
+    dialogue.last_vertical = vertical;
+
+    if !pressed {
+        return;
+    }
+    if !dialogue.line_typed() {
+        dialogue.typed = f32::MAX;
+        return;
+    }
+    if !dialogue.on_last_line() {
+        dialogue.line += 1;
+        dialogue.typed = 0.;
+        return;
+    }
+
+    let next = match dialogue.choices(&flags).get(dialogue.choice) {
+        Some(choice) => choice.next.clone(),
+        None => dialogue.node().and_then(|node| node.next.clone()),
+    };
+    let carry_on = match next {
+        Some(node) => dialogue.enter(&node, &mut flags),
+        None => false,
+    };
+    if !carry_on {
+        commands.remove_resource::<ActiveDialogue>();
+    }
+}
+
+fn spawn_dialogue_box(commands: &mut Commands, assets: &GameAssets) {
+    let font = assets.font(FontEnum::Ui);
+    let style = |font_size: f32| TextStyle {
+        font: font.clone(),
+        font_size,
+        color: Color::WHITE,
+    };
+
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect { bottom: Val::Px(0.), left: Val::Px(0.), ..default() },
+                size: Size::new(Val::Percent(100.), Val::Percent(30.)),
+                padding: UiRect::all(Val::Px(24.)),
+                align_items: AlignItems::FlexStart,
+                ..default()
+            },
+            background_color: BOX_COLOR.into(),
+            focus_policy: FocusPolicy::Pass,
+            z_index: ZIndex::Global(50),
+            ..default()
+        },
+        DialogueBox,
+    )).with_children(|p| {
+        p.spawn((
+            ImageBundle {
+                style: Style {
+                    size: Size::new(Val::Px(PORTRAIT_SIZE), Val::Px(PORTRAIT_SIZE)),
+                    margin: UiRect::right(Val::Px(24.)),
+                    ..default()
+                },
+                ..default()
+            },
+            DialoguePortrait,
+        ));
+        p.spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                flex_grow: 1.,
+                ..default()
+            },
+            focus_policy: FocusPolicy::Pass,
+            ..default()
+        }).with_children(|p| {
+            p.spawn((TextBundle::from_section("", TextStyle { color: SELECTED_COLOR, ..style(24.) }), DialogueSpeaker));
+            p.spawn((TextBundle::from_section("", style(28.)), DialogueText));
+            p.spawn((TextBundle::from_sections([]), DialogueChoices));
+        });
+    });
+}
+
+fn draw_dialogue(
+    mut commands: Commands,
+    dialogue: Option<Res<ActiveDialogue>>,
+    flags: Res<StoryFlags>,
+    assets: Res<GameAssets>,
+    boxes: Query<Entity, With<DialogueBox>>,
+    mut portraits: Query<(&mut UiImage, &mut Visibility), With<DialoguePortrait>>,
+    mut texts: ParamSet<(
+        Query<&mut Text, With<DialogueSpeaker>>,
+        Query<&mut Text, With<DialogueText>>,
+        Query<&mut Text, With<DialogueChoices>>,
+    )>,
+) {
+    let Some(dialogue) = dialogue
+        else {
+            for entity in boxes.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+            return;
+        };
+    if boxes.is_empty() {
+        spawn_dialogue_box(&mut commands, &assets);
+        return;
+    }
+    let Some(line) = dialogue.current_line() else { return; };
+
+    for (mut image, mut visibility) in portraits.iter_mut() {
+        visibility.is_visible = line.portrait.is_some();
+        if let Some(portrait) = line.portrait {
+            image.0 = assets.get(portrait);
+        }
+    }
+    for mut text in texts.p0().iter_mut() {
+        if text.sections[0].value != line.speaker {
+            text.sections[0].value = line.speaker.clone();
+        }
+    }
+    let typed: String = line.text.chars().take(dialogue.typed as usize).collect();
+    for mut text in texts.p1().iter_mut() {
+        if text.sections[0].value != typed {
+            text.sections[0].value = typed.clone();
+        }
+    }
+
+    let choices = dialogue.choices(&flags);
+    let font = assets.font(FontEnum::Ui);
+    for mut text in texts.p2().iter_mut() {
+        text.sections = choices.iter().enumerate().map(|(i, choice)| {
+            let selected = i == dialogue.choice;
+            TextSection::new(
+                format!("{} {}\n", if selected { ">" } else { " " }, choice.text),
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 24.,
+                    color: if selected { SELECTED_COLOR } else { Color::WHITE },
+                },
+            )
+        }).collect();
+    }
+}
+
+fn show_dialogue_box(visible: bool) -> impl FnMut(Query<&mut Visibility, With<DialogueBox>>) {
+    move |mut boxes: Query<&mut Visibility, With<DialogueBox>>| {
+        for mut visibility in boxes.iter_mut() {
+            visibility.is_visible = visible;
+        }
+    }
+}
+
+fn end_dialogue(mut commands: Commands, boxes: Query<Entity, With<DialogueBox>>) {
+    commands.remove_resource::<ActiveDialogue>();
+    for entity in boxes.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use crate::init_systems::{LevelState, Mousey};
+    use crate::interaction::{InteractAction, Interactable, Interacting};
+    use crate::keyboard_input::PlayerInput;
+    use crate::player::Player;
+    use crate::save::Character;
+    use crate::story::{StoryFlag, StoryFlags};
+    use crate::test_app::{mousey_freed, TestApp};
+    use super::ActiveDialogue;
+
+    #[test]
+    fn talking_to_the_girl_holds_the_player_still() {
+        let mut app = TestApp::new();
+        // On the living room floor below the girl, out of the sliding door's range.
+        app.start_as(LevelState::HouseInside, Character::Trunk, (400., -215.), default());
+        let trunk = app.single::<With<Player>>();
+
+        app.step_until(30, "Trunk noticing the girl", |world| world.get::<Interacting>(trunk).is_some());
+        app.press_interact();
+        assert!(app.0.world.contains_resource::<ActiveDialogue>());
+        assert!(app.0.world.resource::<StoryFlags>().is_set(StoryFlag::MetGirl));
+
+        let start = app.0.world.get::<Transform>(trunk).unwrap().translation;
+        for _ in 0..30 {
+            app.input().movement = Vec2::X;
+            app.step();
+        }
+        let end = app.0.world.get::<Transform>(trunk).unwrap().translation;
+        assert!(start.distance(end) < 1., "Trunk moved from {start} to {end} while talking");
+
+        for _ in 0..10 {
+            if !app.0.world.contains_resource::<ActiveDialogue>() {
+                break;
+            }
+            app.press_interact();
+        }
+        assert!(!app.0.world.contains_resource::<ActiveDialogue>());
+    }
+
+    #[test]
+    fn mousey_can_find_the_bird_in_the_back_yard() {
+        let mut app = TestApp::new();
+        // On the sliding door's spawn point in the living room.
+        app.start_as(LevelState::HouseInside, Character::Mousey, (300., -150.), mousey_freed());
+        let mousey = app.single::<With<Mousey>>();
+        app.go_through_door(mousey, LevelState::HouseBack);
+
+        // Across the lawn to the bird.
+        app.step_until(600, "Mousey reaching the bird", |world| {
+            let talk = world.get::<Interacting>(mousey)
+                .and_then(|interacting| world.get::<Interactable>(interacting.entity))
+                .map_or(false, |interactable| matches!(interactable.action, InteractAction::Talk(_)));
+            world.resource_mut::<PlayerInput>().movement = if talk { Vec2::ZERO } else { Vec2::X };
+            talk
+        });
+        app.press_interact();
+        assert!(app.0.world.contains_resource::<ActiveDialogue>());
+        assert!(app.0.world.resource::<StoryFlags>().is_set(StoryFlag::MetBird));
+    }
+}
@@ -0,0 +1,133 @@
+use bevy::input::gamepad::{GamepadEvent, GamepadEventType};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::keyboard_input::{keyboard_input, PlayerInput};
+use crate::player::DEADZONE;
+use crate::schedule::GameSystems;
+use crate::settings::GameSettings;
+
+const DPAD: [(GamepadButtonType, Vec2); 4] = [
+    (GamepadButtonType::DPadUp, Vec2::Y),
+    (GamepadButtonType::DPadDown, Vec2::NEG_Y),
+    (GamepadButtonType::DPadLeft, Vec2::NEG_X),
+    (GamepadButtonType::DPadRight, Vec2::X),
+];
+const INTERACT_BUTTON: GamepadButtonType = GamepadButtonType::South;
+const ATTACK_BUTTON: GamepadButtonType = GamepadButtonType::West;
+const SWITCH_BUTTON: GamepadButtonType = GamepadButtonType::North;
+
+pub struct GamepadInputPlugin;
+
+impl Plugin for GamepadInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActiveDevice>()
+            .add_system(gamepad_connections)
+            .add_system(pick_active_device.after(gamepad_connections))
+            .add_system(gamepad_input.label(GameSystems::Input).after(pick_active_device).after(keyboard_input));
+    }
+}
+
+/// Which device the player is allowed to play with, chosen in the settings menu.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum InputDevice {
+    /// Whichever was used last.
+    #[default]
+    Auto,
+    Keyboard,
+    Gamepad,
+}
+
+impl InputDevice {
+    pub fn next(&self) -> Self {
+        match self {
+            InputDevice::Auto => InputDevice::Keyboard,
+            InputDevice::Keyboard => InputDevice::Gamepad,
+            InputDevice::Gamepad => InputDevice::Auto,
+        }
+    }
+}
+
+/// The device `PlayerInput` is currently read from.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ActiveDevice {
+    #[default]
+    Keyboard,
+    Gamepad(Gamepad),
+}
+
+fn left_stick(axes: &Axis<GamepadAxis>, pad: Gamepad) -> Vec2 {
+    let axis = |axis_type| axes.get(GamepadAxis::new(pad, axis_type)).unwrap_or(0.);
+    Vec2::new(axis(GamepadAxisType::LeftStickX), axis(GamepadAxisType::LeftStickY))
+}
+
+fn gamepad_connections(mut events: EventReader<GamepadEvent>, mut active: ResMut<ActiveDevice>) {
+    for ev in events.iter() {
+        match &ev.event_type {
+            GamepadEventType::Connected(info) => info!("gamepad {} connected: {}", ev.gamepad.id, info.name),
+            GamepadEventType::Disconnected => {
+                info!("gamepad {} disconnected", ev.gamepad.id);
+                if *active == ActiveDevice::Gamepad(ev.gamepad) {
+                    *active = ActiveDevice::Keyboard;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn pick_active_device(
+    settings: Res<GameSettings>,
+    gamepads: Res<Gamepads>,
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut active: ResMut<ActiveDevice>,
+) {
+    let used_pad = gamepads.iter().find(|&pad| {
+        buttons.get_just_pressed().any(|button| button.gamepad == pad)
+            || left_stick(&axes, pad).length() > DEADZONE
+    });
+
+    let next = match settings.input_device {
+        InputDevice::Keyboard => ActiveDevice::Keyboard,
+        InputDevice::Gamepad => {
+            let current = match *active {
+                ActiveDevice::Gamepad(pad) if gamepads.contains(pad) => Some(pad),
+                _ => None,
+            };
+            // Falls back to the keyboard while no gamepad is plugged in.
+            used_pad.or(current).or_else(|| gamepads.iter().next())
+                .map_or(ActiveDevice::Keyboard, ActiveDevice::Gamepad)
+        }
+        InputDevice::Auto => match used_pad {
+            Some(pad) => ActiveDevice::Gamepad(pad),
+            None if keys.get_just_pressed().next().is_some() => ActiveDevice::Keyboard,
+            None => *active,
+        },
+    };
+    if *active != next {
+        *active = next;
+    }
+}
+
+pub fn gamepad_input(
+    active: Res<ActiveDevice>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut input: ResMut<PlayerInput>,
+) {
+    let ActiveDevice::Gamepad(pad) = *active else { return; };
+
+    let mut stick = left_stick(&axes, pad);
+    if stick.length() < DEADZONE {
+        stick = Vec2::ZERO;
+    }
+    let dpad = DPAD.iter()
+        .filter(|(button, _)| buttons.pressed(GamepadButton::new(pad, *button)))
+        .fold(Vec2::ZERO, |acc, (_, dir)| acc + *dir);
+
+    input.movement = (stick + dpad).clamp_length_max(1.);
+    input.just_interacted = buttons.just_pressed(GamepadButton::new(pad, INTERACT_BUTTON));
+    input.just_attacked = buttons.just_pressed(GamepadButton::new(pad, ATTACK_BUTTON));
+    input.just_switched = buttons.just_pressed(GamepadButton::new(pad, SWITCH_BUTTON));
+}
@@ -1,166 +1,26 @@
-use bevy::prelude::{Audio, Commands, Component, default, Res, SpriteBundle, Transform, TransformBundle};
-use bevy::math::Vec3;
-use bevy_rapier2d::geometry::Collider;
-use bevy_rapier2d::math::Vect;
-use bevy::hierarchy::BuildChildren;
-use bevy_rapier2d::prelude::{ActiveEvents, Sensor};
-use crate::assets::{GameAssets, SpriteEnum};
+use bevy::prelude::{Audio, Local, Res};
+use serde::Deserialize;
+use crate::assets::GameAssets;
 use crate::assets::AudioEnum::MusicMainTheme;
-use crate::assets::SpriteEnum::HouseFront;
-use crate::init_systems::{AutoSortOnY, TRUNK_SCALE, YOffset};
-use crate::utils::Interactable;
+use crate::init_systems::LevelState;
 
-pub const HOUSE_FRONT_SCALE: f32 = 0.15;
-
-pub fn init_background(mut commands: Commands, assets: Res<GameAssets>) {
-    commands
-        .spawn(SpriteBundle {
-            texture: assets.sprites.get(&HouseFront).unwrap().clone(),
-            transform: Transform {
-                scale: Vec3::ONE * HOUSE_FRONT_SCALE,
-                translation: Vec3::new(0., 0., -500.),
-                ..default()
-            },
-            ..default()
-        })
-        .with_children(|p| {
-            p.spawn(Collider::ball(100.))
-                .insert(TransformBundle::from(Transform {
-                    translation: Vec3::new(-222., 4., 0.) / HOUSE_FRONT_SCALE,
-                    scale: Vec3::new(3., 1., 1.),
-                    ..default()
-                }));
-            p.spawn(Collider::ball(100.))
-                .insert(TransformBundle::from(Transform {
-                    translation: Vec3::new(-532., -60., 0.) / HOUSE_FRONT_SCALE,
-                    scale: Vec3::new(3., 1., 1.),
-                    ..default()
-                }));
-            p.spawn(Collider::polyline(
-                vec![
-                    Vect::new(121., -13.),
-                    Vect::new(464., -155.),
-                    Vect::new(594., -80.),
-                    Vect::new(716., -118.),
-                    Vect::new(715., 270.),
-                ],
-                Some(vec![[0, 1], [1, 2], [2, 3], [3, 4], [4, 0]]),
-            ));
-            p.spawn(Collider::polyline(
-                vec![
-                    Vect::new(-716., 33.),
-                    Vect::new(-715., -256.),
-                    Vect::new(713., -256.),
-                    Vect::new(713., 69.),
-                    Vect::new(162., 28.),
-                    Vect::new(-255., 70.),
-                ],
-                Some(vec![[0, 1], [1, 2], [2, 3], [3, 4], [4, 5], [5, 0]]),
-            ));
-            p.spawn(Collider::ball(200.))
-                .insert(TransformBundle::from(Transform {
-                    translation: Vec3::new(561., -120., 0.) / HOUSE_FRONT_SCALE,
-                    ..default()
-                }));
-        });
-
-    commands.spawn(SpriteBundle {
-        texture: assets
-            .sprites
-            .get(&SpriteEnum::HouseFrontBackground)
-            .unwrap()
-            .clone(),
-        transform: Transform {
-            scale: Vec3::ONE * 0.14,
-            translation: Vec3::new(0., 0., -501.),
-            ..default()
-        },
-        ..default()
-    });
-
-    commands
-        .spawn(SpriteBundle {
-            texture: assets
-                .sprites
-                .get(&SpriteEnum::HouseFrontHouse)
-                .unwrap()
-                .clone(),
-            transform: Transform {
-                scale: Vec3::ONE * HOUSE_FRONT_SCALE,
-                ..default()
-            },
-            ..default()
-        })
-        .insert(YOffset(-13.))
-        .insert(AutoSortOnY);
-
-    commands
-        .spawn(SpriteBundle {
-            texture: assets
-                .sprites
-                .get(&SpriteEnum::HouseFrontTree1)
-                .unwrap()
-                .clone(),
-            transform: Transform {
-                scale: Vec3::ONE * HOUSE_FRONT_SCALE,
-                ..default()
-            },
-            ..default()
-        })
-        .insert(YOffset(-41.5))
-        .insert(AutoSortOnY);
-
-    commands
-        .spawn(SpriteBundle {
-            texture: assets
-                .sprites
-                .get(&SpriteEnum::HouseFrontTree2)
-                .unwrap()
-                .clone(),
-            transform: Transform {
-                scale: Vec3::ONE * HOUSE_FRONT_SCALE,
-                ..default()
-            },
-            ..default()
-        })
-        .insert(YOffset(9.7))
-        .insert(AutoSortOnY);
-
-    commands
-        .spawn(SpriteBundle {
-            texture: assets.get(SpriteEnum::TrashCan),
-            transform: Transform {
-                translation: Vec3::new(561., -91., 0.),
-                scale: Vec3::ONE * HOUSE_FRONT_SCALE,
-                ..default()
-            },
-            ..default()
-        })
-        .insert(AutoSortOnY)
-        .insert(YOffset(-25.));
-}
-
-#[derive(Component)]
-pub struct DoorInter;
-
-pub fn init_door(
-    mut commands: Commands,
-) {
-    commands.spawn(Collider::ball(300.))
-        .insert(TransformBundle::from(Transform {
-            translation: Vec3::new(347., -105., 0.),
-            scale: Vec3::new(3., 2., 1.) * TRUNK_SCALE,
-            ..default()
-        }))
-        .insert(ActiveEvents::COLLISION_EVENTS)
-        .insert(Sensor)
-        .insert(Interactable)
-        .insert(DoorInter);
+/// Takes whoever uses it to the `spawn_point` of the `target` level.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Door {
+    pub target: LevelState,
+    pub spawn_point: String,
 }
 
+/// Starts the theme the first time the main menu opens, so quitting to the menu and starting
+/// another game doesn't play a second copy over the first.
 pub fn init_music(
     audio: Res<Audio>,
     assets: Res<GameAssets>,
+    mut started: Local<bool>,
 ) {
+    if *started {
+        return;
+    }
+    *started = true;
     audio.play(assets.audio.get(&MusicMainTheme).unwrap().clone());
 }
@@ -0,0 +1,329 @@
+use bevy::asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset};
+use bevy::core_pipeline::clear_color::ClearColorConfig;
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::HashMap;
+use bevy_rapier2d::prelude::{Collider, Sensor};
+use serde::Deserialize;
+use crate::assets::{AppState, GameAssets};
+use crate::camera::{CameraBounds, LockedCamera, MainCamera};
+use crate::init_systems::{AutoSortOnY, CAMERA_LAYER, CAMERA_SCALE, LevelState, YOffset};
+use crate::init_systems::environment::Door;
+use crate::interaction::{DEFAULT_RANGE, InteractAction, InteractCondition, Interactable};
+use crate::story::{StoryChanged, StoryCondition, StoryFlags};
+
+/// A room, loaded from an `assets/levels/*.level.ron` file listed in the asset manifest.
+/// All positions are in world space.
+#[derive(Deserialize, TypeUuid, Clone, Debug)]
+#[uuid = "88cf9c10-8c95-4e6c-879f-0362cd01da3e"]
+pub struct LevelDef {
+    pub camera: CameraDef,
+    #[serde(default)]
+    pub layers: Vec<LayerDef>,
+    #[serde(default)]
+    pub colliders: Vec<ColliderDef>,
+    #[serde(default)]
+    pub interactables: Vec<InteractableDef>,
+    #[serde(default)]
+    pub spawn_points: Vec<SpawnPointDef>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct CameraDef {
+    pub position: (f32, f32),
+    pub bounds: (f32, f32),
+    #[serde(default)]
+    pub locked: bool,
+}
+
+/// Props drawn at the same depth. Props on a `sort_on_y` layer get `AutoSortOnY` instead.
+#[derive(Deserialize, Clone, Debug)]
+pub struct LayerDef {
+    #[serde(default)]
+    pub z: f32,
+    #[serde(default)]
+    pub sort_on_y: bool,
+    pub props: Vec<PropDef>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct PropDef {
+    /// Manifest key of the sprite.
+    pub sprite: String,
+    #[serde(default)]
+    pub position: (f32, f32),
+    #[serde(default = "one")]
+    pub scale: f32,
+    #[serde(default)]
+    pub flip_x: bool,
+    #[serde(default)]
+    pub y_offset: Option<f32>,
+    /// Degrees, anticlockwise.
+    #[serde(default)]
+    pub rotation: f32,
+    /// The prop is only shown while all of these hold.
+    #[serde(default)]
+    pub conditions: Vec<StoryCondition>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub enum ColliderDef {
+    /// `stretch` scales the ball on each axis, giving an ellipse.
+    Ball {
+        position: (f32, f32),
+        radius: f32,
+        #[serde(default = "no_stretch")]
+        stretch: (f32, f32),
+    },
+    Cuboid {
+        position: (f32, f32),
+        half_extents: (f32, f32),
+    },
+    /// A chain of segments. `closed` joins the last point back to the first.
+    Polyline {
+        points: Vec<(f32, f32)>,
+        #[serde(default)]
+        closed: bool,
+    },
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub enum InteractableKind {
+    Door {
+        target: LevelState,
+        /// Name of the spawn point in `target` to arrive at.
+        spawn_point: String,
+    },
+    /// Someone to talk to. `dialogue` is the dialogue's key in the asset manifest.
+    Talk {
+        dialogue: String,
+    },
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct InteractableDef {
+    pub kind: InteractableKind,
+    pub collider: ColliderDef,
+    /// Defaults to one that fits the kind.
+    #[serde(default)]
+    pub prompt: Option<String>,
+    #[serde(default = "default_range")]
+    pub range: f32,
+    #[serde(default)]
+    pub conditions: Vec<InteractCondition>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct SpawnPointDef {
+    pub name: String,
+    pub position: (f32, f32),
+}
+
+fn one() -> f32 {
+    1.
+}
+
+fn no_stretch() -> (f32, f32) {
+    (1., 1.)
+}
+
+fn default_range() -> f32 {
+    DEFAULT_RANGE
+}
+
+#[derive(Default)]
+pub struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let level: LevelDef = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(level));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
+
+/// Marks an entity as belonging to a level, so it is despawned when that level is left.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct LevelEntity(pub LevelState);
+
+/// A prop that comes and goes with the story.
+#[derive(Component, Clone, Debug)]
+pub struct StoryConditional(pub Vec<StoryCondition>);
+
+/// Named positions in the current level, for placing characters.
+#[derive(Resource, Default)]
+pub struct SpawnPoints(pub HashMap<String, Vec2>);
+
+impl SpawnPoints {
+    pub fn get(&self, name: &str) -> Vec2 {
+        match self.0.get(name) {
+            Some(&pos) => pos,
+            None => {
+                warn!("level has no spawn point `{name}`");
+                Vec2::ZERO
+            }
+        }
+    }
+}
+
+pub fn spawn_level(
+    mut commands: Commands,
+    app_state: Res<State<AppState>>,
+    level: Res<State<LevelState>>,
+    levels: Res<Assets<LevelDef>>,
+    assets: Res<GameAssets>,
+    flags: Res<StoryFlags>,
+    mut spawn_points: ResMut<SpawnPoints>,
+) {
+    // `LevelState` is entered at startup too, before there is anything to spawn it with.
+    if *app_state.current() != AppState::InGame {
+        return;
+    }
+    let Some(def) = assets.levels.get(level.current()).and_then(|handle| levels.get(handle))
+        else {
+            error!("no level data for {:?}", level.current());
+            return;
+        };
+    let owner = LevelEntity(*level.current());
+
+    for layer in def.layers.iter() {
+        for prop in layer.props.iter() {
+            let Some(texture) = assets.get_named(&prop.sprite)
+                else {
+                    warn!("level prop uses unknown sprite `{}`", prop.sprite);
+                    continue;
+                };
+            let mut entity = commands.spawn((SpriteBundle {
+                texture,
+                sprite: Sprite {
+                    flip_x: prop.flip_x,
+                    ..default()
+                },
+                transform: Transform {
+                    translation: Vec2::from(prop.position).extend(layer.z),
+                    rotation: Quat::from_rotation_z(prop.rotation.to_radians()),
+                    scale: Vec3::ONE * prop.scale,
+                },
+                visibility: Visibility { is_visible: flags.check(&prop.conditions) },
+                ..default()
+            }, owner));
+            if layer.sort_on_y {
+                entity.insert(AutoSortOnY);
+            }
+            if let Some(y_off) = prop.y_offset {
+                entity.insert(YOffset(y_off));
+            }
+            if !prop.conditions.is_empty() {
+                entity.insert(StoryConditional(prop.conditions.clone()));
+            }
+        }
+    }
+
+    for collider in def.colliders.iter() {
+        commands.spawn((collider_bundle(collider), owner));
+    }
+
+    for interactable_def in def.interactables.iter() {
+        let (action, prompt) = match &interactable_def.kind {
+            InteractableKind::Door { target, spawn_point } => (
+                InteractAction::Door(Door {
+                    target: *target,
+                    spawn_point: spawn_point.clone(),
+                }),
+                "Open door",
+            ),
+            InteractableKind::Talk { dialogue } => (InteractAction::Talk(dialogue.clone()), "Talk"),
+        };
+        let mut interactable = Interactable::new(action, interactable_def.prompt.as_deref().unwrap_or(prompt))
+            .with_range(interactable_def.range);
+        interactable.conditions = interactable_def.conditions.clone();
+        commands.spawn((collider_bundle(&interactable_def.collider), owner, Sensor, interactable));
+    }
+
+    spawn_points.0 = def.spawn_points.iter()
+        .map(|point| (point.name.clone(), Vec2::from(point.position)))
+        .collect();
+
+    let mut camera = commands.spawn((
+        Camera2dBundle {
+            transform: Transform {
+                translation: Vec2::from(def.camera.position).extend(CAMERA_LAYER),
+                ..default()
+            },
+            camera_2d: Camera2d {
+                clear_color: ClearColorConfig::Custom(Color::rgb(0., 0., 0.)),
+            },
+            projection: OrthographicProjection {
+                scale: CAMERA_SCALE,
+                ..default()
+            },
+            ..default()
+        },
+        CameraBounds(def.camera.bounds.0, def.camera.bounds.1),
+        MainCamera,
+        owner,
+    ));
+    if def.camera.locked {
+        camera.insert(LockedCamera);
+    }
+}
+
+/// Shows and hides props as the story moves on while the level is up.
+pub fn update_story_props(
+    mut changes: EventReader<StoryChanged>,
+    flags: Res<StoryFlags>,
+    mut props: Query<(&StoryConditional, &mut Visibility)>,
+) {
+    if changes.iter().count() == 0 {
+        return;
+    }
+    for (conditional, mut visibility) in props.iter_mut() {
+        visibility.is_visible = flags.check(&conditional.0);
+    }
+}
+
+fn collider_bundle(def: &ColliderDef) -> (Collider, TransformBundle) {
+    match def {
+        ColliderDef::Ball { position, radius, stretch } => (
+            Collider::ball(*radius),
+            TransformBundle::from(Transform {
+                translation: Vec2::from(*position).extend(0.),
+                scale: Vec2::from(*stretch).extend(1.),
+                ..default()
+            }),
+        ),
+        ColliderDef::Cuboid { position, half_extents } => (
+            Collider::cuboid(half_extents.0, half_extents.1),
+            TransformBundle::from(Transform::from_translation(Vec2::from(*position).extend(0.))),
+        ),
+        ColliderDef::Polyline { points, closed } => {
+            let vertices: Vec<Vec2> = points.iter().map(|&p| Vec2::from(p)).collect();
+            let indices = closed.then(|| {
+                let n = vertices.len() as u32;
+                (0..n).map(|i| [i, (i + 1) % n]).collect()
+            });
+            (Collider::polyline(vertices, indices), TransformBundle::default())
+        }
+    }
+}
+
+/// Builds an `on_exit` system that despawns everything tagged with `level`.
+pub fn despawn_level(level: LevelState) -> impl FnMut(Commands, Query<(Entity, &LevelEntity)>) {
+    move |mut commands: Commands, q: Query<(Entity, &LevelEntity)>| {
+        for (entity, owner) in q.iter() {
+            if owner.0 == level {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+}
@@ -1,18 +1,25 @@
 pub mod environment;
-pub mod house_inside;
+pub mod level;
 
-use crate::animations::{Animation, Animations, Animator, AnimEnum};
+use std::collections::HashMap;
+use crate::animations::{Animation, Animations, Animator, AnimEnum, Clip, Locomotion};
 use crate::assets::{AppState, GameAssets, SpriteEnum};
-use crate::camera::{CameraBounds, MainCamera};
-use crate::player::{Flippable, Player, PlayerInteractor, Trunk, TRUNK_FRICTION};
-use bevy::core_pipeline::clear_color::ClearColorConfig;
+use crate::camera::CameraTarget;
+use crate::companion::Companion;
+use crate::interaction::{InteractAction, InteractCondition, Interactable};
+use crate::player::{Flippable, Player, Trunk, TRUNK_FRICTION};
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
 use crate::animations::AnimEnum::MouseyIdle;
 use crate::assets::SpriteEnum::MouseyIdle1;
-use crate::init_systems::environment::init_door;
-use crate::init_systems::LevelState::{HouseFront, HouseInside};
-use crate::utils::{Interactable, mouse_door_anim_finish};
+use crate::init_systems::level::{despawn_level, LevelDef, LevelEntity, LevelLoader, SpawnPoints};
+use crate::init_systems::LevelState::{HouseBack, HouseFront, HouseInside};
+use crate::roster::{ControllableCharacter, ROSTER};
+use crate::save::{Character, LoadedSave};
+use crate::story::{StoryCondition, StoryFlag, StoryFlags};
+use crate::utils::walking_mousey_bundle;
 
 const TRUNK_COLLIDER_RADIUS: f32 = 150.;
 const TRUNK_COLLIDER_Y_OFFSET: f32 = -100.;
@@ -22,7 +29,7 @@ pub const TRUNK_SCALE: f32 = 0.075;
 const CAMERA_LAYER: f32 = 100.;
 const CAMERA_SCALE: f32 = 0.5;
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Reflect, Serialize, Deserialize)]
 pub enum LevelState {
     HouseFront,
     HouseInside,
@@ -33,27 +40,42 @@ pub struct EnvironmentInitPlugin;
 
 impl Plugin for EnvironmentInitPlugin {
     fn build(&self, app: &mut App) {
+        app.add_asset::<LevelDef>()
+            .init_asset_loader::<LevelLoader>()
+            .init_resource::<SpawnPoints>()
+            .init_resource::<CharacterPlaces>();
+
         app.add_system_set(
             SystemSet::on_enter(AppState::InGame)
-                .with_system(init_player)
-                .with_system(init_camera)
-                .with_system(init_mousey)
-                .with_system(environment::init_background)
-                .with_system(init_door)
+                .with_system(level::spawn_level)
+                .with_system(init_characters.after(level::spawn_level))
+        );
+        app.add_system_set(
+            SystemSet::on_enter(AppState::MainMenu)
                 .with_system(environment::init_music)
         );
-        app.add_state(LevelState::HouseFront);
-
         app.add_system_set(
-            SystemSet::on_enter(HouseInside)
-                .with_system(house_inside::init_background)
-                .with_system(house_inside::init_camera)
-                .with_system(house_inside::init_mousey)
+            SystemSet::on_exit(AppState::InGame)
+                .with_system(teardown_game)
         );
         app.add_system_set(
-            SystemSet::on_update(HouseInside)
-                .with_system(mouse_door_anim_finish)
+            SystemSet::on_update(AppState::InGame)
+                .with_system(level::update_story_props)
         );
+        app.add_state(LevelState::HouseFront);
+
+        for state in [HouseFront, HouseInside, HouseBack] {
+            app.add_system_set(
+                SystemSet::on_enter(state)
+                    .with_system(level::spawn_level)
+                    .with_system(respawn_characters.after(level::spawn_level))
+            );
+            app.add_system_set(
+                SystemSet::on_exit(state)
+                    .with_system(remember_characters)
+                    .with_system(despawn_level(state))
+            );
+        }
 
         if app.is_plugin_added::<RapierPhysicsPlugin>() {
             app.add_startup_system(init_gravity);
@@ -70,15 +92,79 @@ pub struct AutoSortOnY;
 #[derive(Component, Copy, Clone, Reflect)]
 pub struct YOffset(pub f32);
 
-fn init_player(mut commands: Commands, assets: Res<GameAssets>, animations: Res<Animations>) {
-    commands
-        .spawn((
+/// Where each character was last seen, so they are still there when the player comes back to
+/// that level, or loads a save. Anyone not in here yet is where a new game puts them, in front of
+/// the house.
+#[derive(Resource, Serialize, Deserialize, Clone, Default, Debug)]
+pub struct CharacterPlaces(HashMap<Character, (LevelState, Vec2)>);
+
+impl CharacterPlaces {
+    pub fn get(&self, character: Character) -> (LevelState, Option<Vec2>) {
+        match self.0.get(&character) {
+            Some(&(level, position)) => (level, Some(position)),
+            None => (HouseFront, None),
+        }
+    }
+
+    pub fn set(&mut self, character: Character, level: LevelState, position: Vec2) {
+        self.0.insert(character, (level, position));
+    }
+
+    /// Notes where each of `characters` is now.
+    pub fn update<'a>(
+        &mut self,
+        characters: impl Iterator<Item = (&'a ControllableCharacter, &'a LevelEntity, &'a Transform)>,
+    ) {
+        for (character, owner, trans) in characters {
+            self.set(character.0, owner.0, trans.translation.truncate());
+        }
+    }
+}
+
+/// Everything needed to put the characters placed in the current level into it.
+#[derive(SystemParam)]
+struct CharacterSpawner<'w, 's> {
+    commands: Commands<'w, 's>,
+    assets: Res<'w, GameAssets>,
+    animations: Res<'w, Animations>,
+    spawn_points: Res<'w, SpawnPoints>,
+    level: Res<'w, State<LevelState>>,
+    flags: Res<'w, StoryFlags>,
+    places: ResMut<'w, CharacterPlaces>,
+    present: Query<'w, 's, (&'static ControllableCharacter, &'static LevelEntity)>,
+}
+
+impl CharacterSpawner<'_, '_> {
+    /// Spawns everyone placed in the current level who isn't in it already, like whoever just came
+    /// through the door. `controlled` is given `Player`.
+    fn spawn_missing(&mut self, controlled: Option<Character>) {
+        let level = *self.level.current();
+        for character in ROSTER {
+            let (place, position) = self.places.get(character);
+            let here = self.present.iter().any(|(other, owner)| other.0 == character && owner.0 == level);
+            if place != level || here {
+                continue;
+            }
+            let controlled = controlled == Some(character);
+            match character {
+                Character::Trunk => self.spawn_trunk(position, controlled),
+                Character::Mousey => self.spawn_mousey(position, controlled),
+            }
+        }
+    }
+
+    fn spawn_trunk(&mut self, position: Option<Vec2>, controlled: bool) {
+        let position = position.unwrap_or_else(|| self.spawn_points.get("trunk"));
+        let assets = &self.assets;
+
+        let mut trunk = self.commands.spawn((
             SpriteBundle {
                 texture: assets.sprites.get(&SpriteEnum::TrunkWalk1).unwrap().clone(),
                 ..default()
             },
-            Player,
             Trunk,
+            ControllableCharacter(Character::Trunk),
+            Companion::default(),
             Flippable { right_facing: true },
             AutoSizeOnY,
             AutoSortOnY,
@@ -90,103 +176,149 @@ fn init_player(mut commands: Commands, assets: Res<GameAssets>, animations: Res<
                 ..default()
             },
             RigidBody::Dynamic,
-            Animator::new(animations.get(AnimEnum::TrunkWalk)),
-        ))
-        .insert(SpatialBundle {
-            transform: Transform {
-                translation: Vec3::new(-400., 0., 0.),
-                scale: Vec3::ONE * TRUNK_SCALE,
+            Animator::new(self.animations.get(AnimEnum::TrunkWalk)),
+            Locomotion::new(Clip::Anim(AnimEnum::TrunkIdle), Clip::Anim(AnimEnum::TrunkWalk)),
+            LevelEntity(*self.level.current()),
+        ));
+        if controlled {
+            trunk.insert((Player, CameraTarget));
+        }
+        trunk
+            .insert(SpatialBundle {
+                transform: Transform {
+                    translation: position.extend(0.),
+                    scale: Vec3::ONE * TRUNK_SCALE,
+                    ..default()
+                },
                 ..default()
-            },
-            ..default()
-        })
-        .with_children(|p| {
-            p.spawn(Collider::ball(TRUNK_COLLIDER_RADIUS))
-                .insert(YOffset(TRUNK_COLLIDER_Y_OFFSET))
-                .insert(SpatialBundle {
-                    transform: Transform::from_xyz(0., TRUNK_COLLIDER_Y_OFFSET, 0.),
+            })
+            .with_children(|p| {
+                p.spawn(Collider::ball(TRUNK_COLLIDER_RADIUS))
+                    .insert(YOffset(TRUNK_COLLIDER_Y_OFFSET))
+                    .insert(SpatialBundle {
+                        transform: Transform::from_xyz(0., TRUNK_COLLIDER_Y_OFFSET, 0.),
+                        ..default()
+                    });
+
+                p.spawn(SpriteBundle {
+                    texture: assets.get(SpriteEnum::Shadow),
+                    transform: Transform {
+                        translation: Vec3::new(0., -90. / TRUNK_SCALE, -100.),
+                        ..default()
+                    },
                     ..default()
                 });
 
-            p.spawn(PlayerInteractor)
-                .insert(Collider::ball(700.))
-                .insert(Sensor)
-                .insert(ActiveEvents::COLLISION_EVENTS)
-                .insert(TransformBundle::from(Transform::from_xyz(20., 0., 0.)));
-
-            p.spawn(SpriteBundle {
-                texture: assets.get(SpriteEnum::Shadow),
-                transform: Transform {
-                    translation: Vec3::new(0., -90. / TRUNK_SCALE, -100.),
+                #[cfg(debug_assertions)]
+                p.spawn(SpriteBundle {
+                    texture: assets.sprites.get(&SpriteEnum::DebugCircle).unwrap().clone(),
+                    transform: Transform {
+                        scale: Vec3::ONE * 0.3,
+                        translation: Vec3::new(0., 0., 1.),
+                        ..default()
+                    },
                     ..default()
-                },
-                ..default()
+                });
             });
+    }
+
+    /// Mousey starts out in the trash can, and can't be controlled until freed from it.
+    fn spawn_mousey(&mut self, position: Option<Vec2>, controlled: bool) {
+        let freed = self.flags.is_set(StoryFlag::MouseyFreed);
+        let position = position.unwrap_or_else(|| {
+            self.spawn_points.get(if freed { "mousey_freed" } else { "mousey" })
+        });
 
-            #[cfg(debug_assertions)]
-            p.spawn(SpriteBundle {
-                texture: assets.sprites.get(&SpriteEnum::DebugCircle).unwrap().clone(),
+        let mut mousey = self.commands.spawn(
+            SpriteBundle {
+                texture: self.assets.get(MouseyIdle1),
                 transform: Transform {
-                    scale: Vec3::ONE * 0.3,
-                    translation: Vec3::new(0., 0., 1.),
+                    translation: position.extend(0.),
+                    scale: Vec3::ONE * 0.1,
                     ..default()
                 },
                 ..default()
-            });
-        });
-}
-
-fn init_camera(mut commands: Commands) {
-    commands.spawn((
-        Camera2dBundle {
-            transform: Transform {
-                translation: Vec3::new(-400., 0., CAMERA_LAYER),
-                ..default()
-            },
-            camera_2d: Camera2d {
-                clear_color: ClearColorConfig::Custom(Color::rgb(0., 0., 0.)),
-            },
-            projection: OrthographicProjection {
-                scale: CAMERA_SCALE,
-                ..default()
             },
-            ..default()
-        },
-        CameraBounds(-714., 714.),
-        MainCamera,
-    ));
+        );
+        mousey.insert(AutoSortOnY)
+            .insert(YOffset(-65.))
+            .insert(Mousey)
+            .insert(ControllableCharacter(Character::Mousey))
+            .insert(Companion::default())
+            .insert(Animator::new(self.animations.get(MouseyIdle)))
+            .insert(LevelEntity(*self.level.current()));
+        if freed {
+            mousey.insert(walking_mousey_bundle(&self.animations));
+            if controlled {
+                mousey.insert((Player, CameraTarget));
+            }
+        } else {
+            // The trash can, which only Trunk can knock over.
+            mousey.with_children(|p| {
+                p.spawn(Collider::ball(100.))
+                    .insert(TransformBundle::from(Transform::default()))
+                    .insert(Sensor)
+                    .insert(Interactable::new(InteractAction::FreeMousey, "Knock over")
+                        .with_range(70.)
+                        .with_condition(InteractCondition::Character(Character::Trunk))
+                        .with_condition(InteractCondition::Story(StoryCondition::NotSet(StoryFlag::MouseyFreed))));
+            });
+        }
+    }
 }
 
 #[derive(Component, Copy, Clone)]
 pub struct Mousey;
 
-fn init_mousey(
+/// Places the characters where the save left them, or where a new game starts them, and spawns
+/// whoever is in the starting level. Saves from before everyone's places were kept only know where
+/// the character being controlled was, so the other one starts out in front of the house.
+fn init_characters(mut spawner: CharacterSpawner, save: Option<Res<LoadedSave>>) {
+    let controlled = match save.as_deref() {
+        Some(LoadedSave(save)) => {
+            *spawner.places = save.places.clone();
+            spawner.places.set(save.character, save.level, Vec2::from(save.position));
+            save.character
+        }
+        None => {
+            *spawner.places = CharacterPlaces::default();
+            Character::Trunk
+        }
+    };
+    spawner.spawn_missing(Some(controlled));
+}
+
+/// Puts back whoever was left in a level when it's entered again.
+fn respawn_characters(mut spawner: CharacterSpawner, app_state: Res<State<AppState>>) {
+    // `init_characters` takes care of the level the game starts in.
+    if *app_state.current() != AppState::InGame {
+        return;
+    }
+    spawner.spawn_missing(None);
+}
+
+/// Notes where everyone in the level being left is, before the level's teardown takes them along.
+/// Whoever is going through the door already belongs to the next level.
+fn remember_characters(
+    mut places: ResMut<CharacterPlaces>,
+    q: Query<(&ControllableCharacter, &LevelEntity, &Transform)>,
+) {
+    places.update(q.iter());
+}
+
+/// Despawns the game world when leaving for the main menu, so a new game starts from scratch.
+fn teardown_game(
     mut commands: Commands,
-    assets: Res<GameAssets>,
-    mut anims: Res<Animations>
+    q: Query<Entity, With<LevelEntity>>,
+    mut level: ResMut<State<LevelState>>,
+    mut flags: ResMut<StoryFlags>,
 ) {
-    commands.spawn(
-        SpriteBundle {
-            texture: assets.get(MouseyIdle1),
-            transform: Transform {
-                translation: Vec3::new(565., -52., 0.),
-                scale: Vec3::ONE * 0.1,
-                ..default()
-            },
-            ..default()
-        },
-    ).insert(AutoSortOnY)
-        .insert(YOffset(-65.))
-        .insert(Mousey)
-        .insert(Animator::new(anims.get(MouseyIdle)))
-        .with_children(|p| {
-            p.spawn(Collider::ball(100.))
-                .insert(TransformBundle::from(Transform::default()))
-                .insert(Sensor)
-                .insert(ActiveEvents::COLLISION_EVENTS)
-                .insert(Mousey)
-                .insert(Interactable);
-        });
+    for entity in q.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let _ = level.overwrite_set(HouseFront);
+    *flags = StoryFlags::default();
+    commands.remove_resource::<LoadedSave>();
 }
 
 fn init_gravity(mut config: ResMut<RapierConfiguration>) {
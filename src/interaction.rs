@@ -0,0 +1,317 @@
+use bevy::prelude::*;
+use bevy::window::CursorIcon;
+use bevy_rapier2d::prelude::{QueryFilter, RapierContext};
+use serde::Deserialize;
+use crate::assets::AppState;
+use crate::init_systems::environment::Door;
+use crate::init_systems::Mousey;
+use crate::keyboard_input::PlayerInput;
+use crate::player::{Flippable, Player};
+use crate::save::Character;
+use crate::schedule::GameSystems;
+use crate::story::{StoryCondition, StoryFlags};
+use crate::utils::{free_mousey, use_door};
+
+/// How close the player has to be to an interactable to use it, unless it says otherwise.
+pub const DEFAULT_RANGE: f32 = 80.;
+
+const HOVER_TINT: Color = Color::rgb(1., 1., 0.6);
+
+pub struct InteractionPlugin;
+
+impl Plugin for InteractionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<InteractEvent>()
+            .add_event::<UseDoor>()
+            .add_event::<FreeMousey>()
+            .add_event::<Talk>()
+            .init_resource::<HoveredInteractable>()
+            // Only needs the mouse position, and click-to-move wants to know what was clicked on.
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .after(GameSystems::Input)
+                    .before(GameSystems::Movement)
+                    .with_system(hover_interactables)
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .label(GameSystems::Interaction)
+                    .after(GameSystems::Movement)
+                    .with_system(pick_interactable)
+                    .with_system(interact_on_press.after(pick_interactable))
+                    .with_system(click_to_interact.after(pick_interactable))
+                    .with_system(dispatch_interactions.after(interact_on_press).after(click_to_interact))
+                    .with_system(free_mousey.after(dispatch_interactions))
+                    .with_system(use_door.after(dispatch_interactions))
+            );
+    }
+}
+
+/// Something the player can walk up to and use. The entity also needs a sensor collider,
+/// so it can be hovered and clicked on.
+#[derive(Component, Clone, Debug)]
+pub struct Interactable {
+    pub action: InteractAction,
+    /// Shown next to the interact button while the player is in range.
+    pub prompt: String,
+    /// Measured from the player to the interactable's centre.
+    pub range: f32,
+    /// All of these must hold for the player to use it.
+    pub conditions: Vec<InteractCondition>,
+}
+
+impl Interactable {
+    pub fn new(action: InteractAction, prompt: impl Into<String>) -> Self {
+        Self {
+            action,
+            prompt: prompt.into(),
+            range: DEFAULT_RANGE,
+            conditions: vec![],
+        }
+    }
+
+    pub fn with_range(mut self, range: f32) -> Self {
+        self.range = range;
+        self
+    }
+
+    pub fn with_condition(mut self, condition: InteractCondition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+
+    pub fn usable_by(&self, character: Character, flags: &StoryFlags) -> bool {
+        self.conditions.iter().all(|condition| match condition {
+            InteractCondition::Character(only) => *only == character,
+            InteractCondition::Story(condition) => flags.check(&[*condition]),
+        })
+    }
+}
+
+/// What happens when an interactable is used. Each action is handed to its own handler as an event.
+#[derive(Clone, Debug)]
+pub enum InteractAction {
+    Door(Door),
+    FreeMousey,
+    /// Starts the dialogue with this key in the asset manifest.
+    Talk(String),
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub enum InteractCondition {
+    /// Only this character can use it.
+    Character(Character),
+    /// Only usable at this point in the story.
+    Story(StoryCondition),
+}
+
+/// The interactable the player would use by pressing interact.
+#[derive(Component)]
+pub struct Interacting {
+    pub entity: Entity
+}
+
+#[derive(Debug)]
+pub struct InteractEvent {
+    pub interactor: Entity,
+    pub interactable: Entity
+}
+
+/// Sent for `InteractAction::Door`.
+pub struct UseDoor {
+    pub user: Entity,
+    pub door: Door,
+}
+
+/// Sent for `InteractAction::Talk`.
+pub struct Talk {
+    pub talker: Entity,
+    pub dialogue: String,
+}
+
+/// Sent for `InteractAction::FreeMousey`.
+pub struct FreeMousey {
+    pub trunk: Entity,
+    /// The trash can's interactable, a child of Mousey.
+    pub interactable: Entity,
+}
+
+fn character(mousey: Option<&Mousey>) -> Character {
+    if mousey.is_some() { Character::Mousey } else { Character::Trunk }
+}
+
+/// Picks the interactable each player would use: the nearest one in range that they're facing,
+/// or the nearest one behind them if there's nothing in front.
+pub fn pick_interactable(
+    mut commands: Commands,
+    players: Query<(Entity, &GlobalTransform, Option<&Sprite>, Option<&Flippable>, Option<&Mousey>, Option<&Interacting>), With<Player>>,
+    interactables: Query<(Entity, &Interactable, &GlobalTransform, Option<&Parent>)>,
+    flags: Res<StoryFlags>,
+) {
+    for (player, trans, sprite, flippable, mousey, current) in players.iter() {
+        let pos = trans.translation().truncate();
+        let facing = match (sprite, flippable) {
+            (Some(sprite), Some(flippable)) if sprite.flip_x == flippable.right_facing => -1.,
+            _ => 1.,
+        };
+
+        let best = interactables.iter()
+            // A character's own interactable (Mousey's) isn't theirs to use.
+            .filter(|(_, _, _, parent)| parent.map_or(true, |p| p.get() != player))
+            .filter(|(_, interactable, _, _)| interactable.usable_by(character(mousey), &flags))
+            .filter_map(|(entity, interactable, i_trans, _)| {
+                let offset = i_trans.translation().truncate() - pos;
+                let distance = offset.length();
+                (distance <= interactable.range).then_some((entity, offset.x * facing < 0., distance))
+            })
+            .min_by(|a, b| (a.1, a.2).partial_cmp(&(b.1, b.2)).unwrap())
+            .map(|(entity, _, _)| entity);
+
+        match (best, current) {
+            (Some(entity), Some(current)) if entity == current.entity => {}
+            (Some(entity), _) => {
+                commands.entity(player).insert(Interacting { entity });
+            }
+            (None, Some(_)) => {
+                commands.entity(player).remove::<Interacting>();
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+pub fn interact_on_press(
+    mut evt_writer: EventWriter<InteractEvent>,
+    q: Query<(Entity, &Interacting), With<Player>>,
+    input: Res<PlayerInput>,
+) {
+    if !input.just_interacted {
+        return;
+    }
+    for (ent, interacting) in q.iter() {
+        evt_writer.send(InteractEvent {
+            interactor: ent,
+            interactable: interacting.entity,
+        });
+    }
+}
+
+/// Hands each interaction to the handler for its action, if the interactor is allowed to use it.
+pub fn dispatch_interactions(
+    mut events: EventReader<InteractEvent>,
+    interactables: Query<&Interactable>,
+    characters: Query<Option<&Mousey>>,
+    flags: Res<StoryFlags>,
+    mut doors: EventWriter<UseDoor>,
+    mut free_mousey: EventWriter<FreeMousey>,
+    mut talks: EventWriter<Talk>,
+) {
+    for ev in events.iter() {
+        let Ok(interactable) = interactables.get(ev.interactable) else { continue; };
+        let Ok(mousey) = characters.get(ev.interactor) else { continue; };
+        if !interactable.usable_by(character(mousey), &flags) {
+            continue;
+        }
+        match &interactable.action {
+            InteractAction::Door(door) => doors.send(UseDoor {
+                user: ev.interactor,
+                door: door.clone(),
+            }),
+            InteractAction::FreeMousey => free_mousey.send(FreeMousey {
+                trunk: ev.interactor,
+                interactable: ev.interactable,
+            }),
+            InteractAction::Talk(dialogue) => talks.send(Talk {
+                talker: ev.interactor,
+                dialogue: dialogue.clone(),
+            }),
+        }
+    }
+}
+
+/// The interactable under the mouse cursor.
+#[derive(Resource, Default)]
+pub struct HoveredInteractable(pub Option<Entity>);
+
+pub fn hover_interactables(
+    rapier: Res<RapierContext>,
+    input: Res<PlayerInput>,
+    interactables: Query<Option<&Parent>, With<Interactable>>,
+    players: Query<(), With<Player>>,
+    mut sprites: Query<&mut Sprite>,
+    mut hovered: ResMut<HoveredInteractable>,
+    mut windows: ResMut<Windows>,
+) {
+    let mut under_cursor = None;
+    rapier.intersections_with_point(input.mouse_pos, QueryFilter::default(), |entity| {
+        let Ok(parent) = interactables.get(entity) else { return true; };
+        // The player's own interactable (Mousey's) isn't something to click on.
+        if parent.map_or(false, |p| players.contains(p.get())) {
+            return true;
+        }
+        under_cursor = Some(entity);
+        false
+    });
+    if under_cursor == hovered.0 {
+        return;
+    }
+
+    // Doors are bare colliders, so the sprite to tint may be the parent's, or there may be none.
+    let mut tint = |entity: Entity, color: Color| {
+        let target = match interactables.get(entity) {
+            Ok(Some(parent)) if !sprites.contains(entity) => parent.get(),
+            _ => entity,
+        };
+        if let Ok(mut sprite) = sprites.get_mut(target) {
+            sprite.color = color;
+        }
+    };
+    if let Some(old) = hovered.0 {
+        tint(old, Color::WHITE);
+    }
+    if let Some(new) = under_cursor {
+        tint(new, HOVER_TINT);
+    }
+    hovered.0 = under_cursor;
+
+    if let Some(window) = windows.get_primary_mut() {
+        window.set_cursor_icon(if under_cursor.is_some() { CursorIcon::Hand } else { CursorIcon::Default });
+    }
+}
+
+/// Whether clicking the interactable uses it right away for this player, rather than walking up to
+/// it: it has to be in range, as `pick_interactable` measures it, and usable by them.
+pub fn in_reach(
+    interactable: &Interactable,
+    at: &GlobalTransform,
+    player: &GlobalTransform,
+    mousey: Option<&Mousey>,
+    flags: &StoryFlags,
+) -> bool {
+    at.translation().truncate().distance(player.translation().truncate()) <= interactable.range
+        && interactable.usable_by(character(mousey), flags)
+}
+
+/// Clicking an interactable in reach uses it, even if it isn't the one pressing interact would.
+pub fn click_to_interact(
+    input: Res<PlayerInput>,
+    hovered: Res<HoveredInteractable>,
+    flags: Res<StoryFlags>,
+    players: Query<(Entity, &GlobalTransform, Option<&Mousey>), With<Player>>,
+    interactables: Query<(&Interactable, &GlobalTransform)>,
+    mut evt_writer: EventWriter<InteractEvent>,
+) {
+    if !input.just_clicked {
+        return;
+    }
+    let Some(target) = hovered.0 else { return; };
+    let Ok((interactable, at)) = interactables.get(target) else { return; };
+    for (ent, trans, mousey) in players.iter() {
+        if in_reach(interactable, at, trans, mousey, &flags) {
+            evt_writer.send(InteractEvent {
+                interactor: ent,
+                interactable: target,
+            });
+        }
+    }
+}
@@ -1,18 +1,160 @@
 use crate::camera::MainCamera;
+use crate::schedule::GameSystems;
+use crate::settings::project_dirs;
 use bevy::app::{App, Plugin};
 use bevy::input::Input;
+use bevy::log::{info, warn};
 use bevy::prelude::{
-    Camera, Component, GlobalTransform, KeyCode, Query, Res, ResMut, Resource, Vec2, Windows, With,
+    Camera, Component, EventReader, GlobalTransform, IntoSystemDescriptor, KeyCode, MouseButton, Query, Res, ResMut,
+    Resource, Vec2, Windows, With,
 };
+use bevy::window::WindowFocused;
 use bevy::render::camera::RenderTarget;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
 
 #[derive(Resource, Component, Default)]
 pub struct PlayerInput {
     pub movement: Vec2,
     pub mouse_pos: Vec2,
     pub just_clicked: bool,
-    pub just_interacted: bool
+    pub just_interacted: bool,
+    pub just_attacked: bool,
+    pub just_switched: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum InputAction {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Interact,
+    Attack,
+    SwitchCharacter,
+    Pause,
+}
+
+impl InputAction {
+    pub const ALL: [InputAction; 8] = [
+        InputAction::MoveUp,
+        InputAction::MoveDown,
+        InputAction::MoveLeft,
+        InputAction::MoveRight,
+        InputAction::Interact,
+        InputAction::Attack,
+        InputAction::SwitchCharacter,
+        InputAction::Pause,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            InputAction::MoveUp => "Move up",
+            InputAction::MoveDown => "Move down",
+            InputAction::MoveLeft => "Move left",
+            InputAction::MoveRight => "Move right",
+            InputAction::Interact => "Interact",
+            InputAction::Attack => "Attack",
+            InputAction::SwitchCharacter => "Switch character",
+            InputAction::Pause => "Pause",
+        }
+    }
+
+    fn direction(&self) -> Option<Vec2> {
+        match self {
+            InputAction::MoveUp => Some(Vec2::Y),
+            InputAction::MoveDown => Some(Vec2::NEG_Y),
+            InputAction::MoveLeft => Some(Vec2::NEG_X),
+            InputAction::MoveRight => Some(Vec2::X),
+            _ => None,
+        }
+    }
+}
+
+/// The keys bound to each action, kept in `input.ron` in the config directory.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+pub struct InputMap(pub BTreeMap<InputAction, Vec<KeyCode>>);
+
+impl Default for InputMap {
+    fn default() -> Self {
+        Self(BTreeMap::from([
+            (InputAction::MoveUp, vec![KeyCode::W, KeyCode::Up]),
+            (InputAction::MoveDown, vec![KeyCode::S, KeyCode::Down]),
+            (InputAction::MoveLeft, vec![KeyCode::A, KeyCode::Left]),
+            (InputAction::MoveRight, vec![KeyCode::D, KeyCode::Right]),
+            (InputAction::Interact, vec![KeyCode::E]),
+            (InputAction::Attack, vec![KeyCode::Space]),
+            (InputAction::SwitchCharacter, vec![KeyCode::Tab]),
+            (InputAction::Pause, vec![KeyCode::Escape]),
+        ]))
+    }
+}
+
+impl InputMap {
+    pub fn keys(&self, action: InputAction) -> &[KeyCode] {
+        self.0.get(&action).map_or(&[], |keys| keys.as_slice())
+    }
+
+    pub fn action_for(&self, key: KeyCode) -> Option<InputAction> {
+        self.0.iter().find(|(_, keys)| keys.contains(&key)).map(|(&action, _)| action)
+    }
+
+    pub fn pressed(&self, action: InputAction, keys: &Input<KeyCode>) -> bool {
+        keys.any_pressed(self.keys(action).iter().copied())
+    }
+
+    pub fn just_pressed(&self, action: InputAction, keys: &Input<KeyCode>) -> bool {
+        keys.any_just_pressed(self.keys(action).iter().copied())
+    }
+
+    /// Like `just_pressed`, but also consumes the press so later systems this frame don't see it.
+    pub fn clear_just_pressed(&self, action: InputAction, keys: &mut Input<KeyCode>) -> bool {
+        self.keys(action).iter().fold(false, |cleared, &key| keys.clear_just_pressed(key) || cleared)
+    }
+
+    /// Makes `key` the only key for `action`, taking it away from any other action.
+    pub fn rebind(&mut self, action: InputAction, key: KeyCode) {
+        for keys in self.0.values_mut() {
+            keys.retain(|&k| k != key);
+        }
+        self.0.insert(action, vec![key]);
+    }
+
+    fn path() -> PathBuf {
+        match project_dirs() {
+            Some(dirs) => dirs.config_dir().join("input.ron"),
+            None => PathBuf::from("input.ron"),
+        }
+    }
+
+    /// Reads the saved bindings, falling back to the defaults for anything missing.
+    pub fn load() -> Self {
+        let mut map = InputMap::default();
+        let Ok(text) = fs::read_to_string(Self::path()) else { return map; };
+        match ron::from_str::<InputMap>(&text) {
+            Ok(saved) => map.0.extend(saved.0),
+            Err(err) => warn!("ignoring unreadable input config {:?}: {err}", Self::path()),
+        }
+        map
+    }
+
+    fn save(&self) {
+        let path = Self::path();
+        let result = ron::ser::to_string_pretty(self, Default::default())
+            .map_err(|err| err.to_string())
+            .and_then(|text| {
+                if let Some(dir) = path.parent() {
+                    fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+                }
+                fs::write(&path, text).map_err(|err| err.to_string())
+            });
+        match result {
+            Ok(()) => info!("saved input config to {path:?}"),
+            Err(err) => warn!("could not save input config to {path:?}: {err}"),
+        }
+    }
 }
 
 pub struct KeyboardInputPlugin;
@@ -20,50 +162,46 @@ pub struct KeyboardInputPlugin;
 impl Plugin for KeyboardInputPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(PlayerInput::default())
-            .add_system(keyboard_input);
+            .insert_resource(InputMap::load())
+            .add_system(release_keys_on_focus_loss.before(keyboard_input))
+            .add_system(keyboard_input.label(GameSystems::Input))
+            .add_system(mouse_position.label(GameSystems::Input))
+            .add_system(mouse_buttons.label(GameSystems::Input))
+            .add_system(save_input_map);
     }
 }
 
-fn keyboard_input(keys: Res<Input<KeyCode>>, mut input: ResMut<PlayerInput>) {
-    let key_to_input_map: HashMap<KeyCode, [f32; 2]> = HashMap::from([
-        (KeyCode::W, [0., 1.]),
-        (KeyCode::A, [-1., 0.]),
-        (KeyCode::S, [0., -1.]),
-        (KeyCode::D, [1., 0.]),
-    ]);
-
-    fn keys_to_vec<'a>(
-        iter: impl ExactSizeIterator<Item = &'a KeyCode> + Sized,
-        key_map: &HashMap<KeyCode, [f32; 2]>,
-    ) -> Option<Vec2> {
-        iter.filter_map(|key_code: &KeyCode| {
-            if let Some(&value) = key_map.get(key_code) {
-                Some(Vec2::from_array(value))
-            } else {
-                None
-            }
-        })
-        .reduce(|acc, curr| acc + curr)
-    }
-
-    let pressed_update = keys_to_vec(keys.get_just_pressed(), &key_to_input_map);
-    let released_update = keys_to_vec(keys.get_just_released(), &key_to_input_map).map(|vec| -vec);
-
-    let input_update = if let Some(pressed) = pressed_update {
-        if let Some(released) = released_update {
-            Some(pressed + released)
-        } else {
-            Some(pressed)
-        }
-    } else {
-        released_update
-    };
+pub fn keyboard_input(keys: Res<Input<KeyCode>>, input_map: Res<InputMap>, mut input: ResMut<PlayerInput>) {
+    // Rebuilt from the held keys every frame, so a missed release can't leave it stuck.
+    input.movement = InputAction::ALL.iter()
+        .filter(|&&action| input_map.pressed(action, &keys))
+        .filter_map(|action| action.direction())
+        .fold(Vec2::ZERO, |acc, dir| acc + dir)
+        .normalize_or_zero();
 
-    if let Some(input_update) = input_update {
-        input.movement += input_update;
+    input.just_interacted = input_map.just_pressed(InputAction::Interact, &keys);
+    input.just_attacked = input_map.just_pressed(InputAction::Attack, &keys);
+    input.just_switched = input_map.just_pressed(InputAction::SwitchCharacter, &keys);
+}
+
+/// Keys released while the window is unfocused never reach us, so forget everything held.
+pub fn release_keys_on_focus_loss(
+    mut events: EventReader<WindowFocused>,
+    mut keys: ResMut<Input<KeyCode>>,
+) {
+    if events.iter().any(|ev| !ev.focused) {
+        keys.reset_all();
     }
+}
 
-    input.just_interacted = keys.just_pressed(KeyCode::E);
+pub fn mouse_buttons(buttons: Res<Input<MouseButton>>, mut input: ResMut<PlayerInput>) {
+    input.just_clicked = buttons.just_pressed(MouseButton::Left);
+}
+
+fn save_input_map(input_map: Res<InputMap>) {
+    if input_map.is_changed() && !input_map.is_added() {
+        input_map.save();
+    }
 }
 
 pub fn mouse_position(
@@ -96,3 +234,95 @@ pub fn mouse_position(
 
     input.mouse_pos = world_pos.truncate();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::app::App;
+    use bevy::prelude::Mut;
+    use bevy::window::WindowId;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.init_resource::<Input<KeyCode>>()
+            .init_resource::<PlayerInput>()
+            .init_resource::<InputMap>()
+            .add_event::<WindowFocused>()
+            .add_system(release_keys_on_focus_loss.before(keyboard_input))
+            .add_system(keyboard_input);
+        app
+    }
+
+    // Runs a frame, then clears the just pressed/released state like `InputPlugin` does.
+    fn frame(app: &mut App) -> Vec2 {
+        app.update();
+        app.world.resource_mut::<Input<KeyCode>>().clear();
+        app.world.resource::<PlayerInput>().movement
+    }
+
+    fn keys(app: &mut App) -> Mut<Input<KeyCode>> {
+        app.world.resource_mut::<Input<KeyCode>>()
+    }
+
+    #[test]
+    fn movement_follows_held_keys() {
+        let mut app = app();
+        keys(&mut app).press(KeyCode::W);
+        assert_eq!(frame(&mut app), Vec2::Y);
+        assert_eq!(frame(&mut app), Vec2::Y);
+        keys(&mut app).release(KeyCode::W);
+        assert_eq!(frame(&mut app), Vec2::ZERO);
+    }
+
+    #[test]
+    fn diagonals_are_normalized() {
+        let mut app = app();
+        keys(&mut app).press(KeyCode::W);
+        keys(&mut app).press(KeyCode::D);
+        let movement = frame(&mut app);
+        assert!((movement.length() - 1.).abs() < 1e-6);
+        assert!(movement.x > 0. && movement.y > 0.);
+    }
+
+    #[test]
+    fn keys_bound_to_the_same_action_do_not_add_up() {
+        let mut app = app();
+        keys(&mut app).press(KeyCode::W);
+        keys(&mut app).press(KeyCode::Up);
+        assert_eq!(frame(&mut app), Vec2::Y);
+        keys(&mut app).release(KeyCode::Up);
+        assert_eq!(frame(&mut app), Vec2::Y);
+    }
+
+    #[test]
+    fn opposite_keys_cancel_out() {
+        let mut app = app();
+        keys(&mut app).press(KeyCode::A);
+        keys(&mut app).press(KeyCode::D);
+        assert_eq!(frame(&mut app), Vec2::ZERO);
+        keys(&mut app).release(KeyCode::A);
+        assert_eq!(frame(&mut app), Vec2::X);
+    }
+
+    #[test]
+    fn no_drift_when_a_release_is_missed() {
+        let mut app = app();
+        keys(&mut app).press(KeyCode::S);
+        assert_eq!(frame(&mut app), Vec2::NEG_Y);
+        // The release happened somewhere we couldn't see it, e.g. another state or the inspector.
+        keys(&mut app).reset_all();
+        assert_eq!(frame(&mut app), Vec2::ZERO);
+        keys(&mut app).press(KeyCode::S);
+        assert_eq!(frame(&mut app), Vec2::NEG_Y);
+    }
+
+    #[test]
+    fn losing_focus_releases_held_keys() {
+        let mut app = app();
+        keys(&mut app).press(KeyCode::D);
+        assert_eq!(frame(&mut app), Vec2::X);
+        app.world.send_event(WindowFocused { id: WindowId::primary(), focused: false });
+        assert_eq!(frame(&mut app), Vec2::ZERO);
+        assert_eq!(frame(&mut app), Vec2::ZERO);
+    }
+}
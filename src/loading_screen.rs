@@ -0,0 +1,120 @@
+use bevy::prelude::*;
+use crate::assets::{AppState, AssetsLoading, FontEnum, GameAssets, load_assets, LoadingProgress};
+use crate::ui::full_screen_column;
+
+const BAR_WIDTH: f32 = 400.;
+const BAR_HEIGHT: f32 = 24.;
+
+pub struct LoadingScreenPlugin;
+
+impl Plugin for LoadingScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_enter(AppState::Loading)
+                .with_system(spawn_loading_screen.after(load_assets))
+        )
+            .add_system_set(
+                SystemSet::on_update(AppState::Loading)
+                    .with_system(update_loading_screen)
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::Loading)
+                    .with_system(despawn_loading_screen)
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::LoadError)
+                    .with_system(show_load_error)
+            );
+    }
+}
+
+#[derive(Component)]
+pub struct LoadingScreen;
+
+#[derive(Component)]
+pub struct LoadingBar;
+
+#[derive(Component)]
+pub struct LoadingText;
+
+fn spawn_loading_screen(mut commands: Commands, assets: Res<GameAssets>) {
+    let style = TextStyle {
+        font: assets.font(FontEnum::Ui),
+        font_size: 28.,
+        color: Color::WHITE,
+    };
+
+    commands.spawn((Camera2dBundle::default(), LoadingScreen));
+    commands
+        .spawn((full_screen_column(Color::BLACK), LoadingScreen))
+        .with_children(|p| {
+            p.spawn((TextBundle::from_section("Loading...", style), LoadingText));
+            p.spawn(NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Px(BAR_WIDTH), Val::Px(BAR_HEIGHT)),
+                    margin: UiRect::top(Val::Px(16.)),
+                    ..default()
+                },
+                background_color: Color::DARK_GRAY.into(),
+                ..default()
+            }).with_children(|p| {
+                p.spawn((
+                    NodeBundle {
+                        style: Style {
+                            size: Size::new(Val::Percent(0.), Val::Percent(100.)),
+                            ..default()
+                        },
+                        background_color: Color::WHITE.into(),
+                        ..default()
+                    },
+                    LoadingBar,
+                ));
+            });
+        });
+}
+
+fn update_loading_screen(
+    progress: Res<LoadingProgress>,
+    mut bar_q: Query<&mut Style, With<LoadingBar>>,
+    mut text_q: Query<&mut Text, With<LoadingText>>,
+) {
+    if !progress.is_changed() {
+        return;
+    }
+    for mut style in bar_q.iter_mut() {
+        style.size.width = Val::Percent(progress.fraction() * 100.);
+    }
+    for mut text in text_q.iter_mut() {
+        text.sections[0].value = format!("Loading... {} / {}", progress.loaded, progress.total);
+    }
+}
+
+fn despawn_loading_screen(mut commands: Commands, q: Query<Entity, With<LoadingScreen>>) {
+    for entity in q.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[derive(Component)]
+pub struct LoadErrorScreen;
+
+fn show_load_error(mut commands: Commands, loading: Res<AssetsLoading>, assets: Res<GameAssets>) {
+    let style = TextStyle {
+        font: assets.font(FontEnum::Ui),
+        font_size: 28.,
+        color: Color::WHITE,
+    };
+
+    commands.spawn((Camera2dBundle::default(), LoadErrorScreen));
+    commands
+        .spawn((full_screen_column(Color::BLACK), LoadErrorScreen))
+        .with_children(|p| {
+            p.spawn(TextBundle::from_section("Some game files could not be loaded:", style.clone()));
+            for path in loading.failed.iter() {
+                p.spawn(TextBundle::from_section(
+                    format!("assets/{path}"),
+                    TextStyle { color: Color::rgb(1., 0.4, 0.4), ..style.clone() },
+                ));
+            }
+        });
+}
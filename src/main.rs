@@ -1,7 +1,17 @@
 use crate::assets::AssetLoaderPlugin;
+use crate::click_to_move::ClickToMovePlugin;
+use crate::gamepad_input::GamepadInputPlugin;
 use crate::init_systems::{EnvironmentInitPlugin, YOffset};
 use crate::keyboard_input::KeyboardInputPlugin;
-use animations::{AnimPlugin, Animator};
+use crate::loading_screen::LoadingScreenPlugin;
+use crate::menu::MenuPlugin;
+use crate::pause::PausePlugin;
+use crate::replay::ReplayPlugin;
+use crate::save::SavePlugin;
+use crate::settings::SettingsPlugin;
+use crate::story::StoryPlugin;
+use crate::transition::TransitionPlugin;
+use animations::AnimPlugin;
 use bevy::app::PluginGroupBuilder;
 use bevy::prelude::*;
 use bevy_embedded_assets::EmbeddedAssetPlugin;
@@ -9,18 +19,40 @@ use bevy_inspector_egui::quick::WorldInspectorPlugin;
 use bevy_rapier2d::prelude::{NoUserData, RapierPhysicsPlugin};
 use bevy_rapier2d::render::RapierDebugRenderPlugin;
 
-use crate::camera::camera_follow;
-use crate::player::{interact_col_event_sys, flip_flippables, flip_interactor, move_player, InteractEvent, interact_events_pt2};
-use player::player_anim_controller;
-use utils::{auto_sort_on_y, reinsert_colliders, update_size_on_y};
-use crate::utils::{attack_system, door_interact, mouse_door_anim_player, mouse_idle_anim, mouse_trash_animator, mouse_walk_anim, mousey_interact};
+use crate::camera::CameraPlugin;
+use crate::companion::CompanionPlugin;
+use crate::dialogue::DialoguePlugin;
+use crate::interaction::InteractionPlugin;
+use crate::player::PlayerPlugin;
+use crate::prompt::PromptPlugin;
+use crate::roster::RosterPlugin;
+use crate::utils::{CutscenePlugin, DepthPlugin};
 
 mod animations;
 mod assets;
 mod camera;
+mod click_to_move;
+mod companion;
+mod dialogue;
+mod gamepad_input;
 mod init_systems;
+mod interaction;
 mod keyboard_input;
+mod loading_screen;
+mod menu;
+mod pause;
 mod player;
+mod prompt;
+mod replay;
+mod roster;
+mod save;
+mod schedule;
+mod settings;
+mod story;
+#[cfg(test)]
+mod test_app;
+mod transition;
+mod ui;
 mod utils;
 
 fn main() {
@@ -41,35 +73,46 @@ fn main() {
         .add_plugin(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(100.))
         .add_plugins(DebugPlugins)
         .add_plugin(AssetLoaderPlugin)
+        .add_plugin(LoadingScreenPlugin)
+        .add_plugin(MenuPlugin)
+        .add_plugin(SettingsPlugin)
+        .add_plugin(PausePlugin)
+        .add_plugin(TransitionPlugin)
+        .add_plugin(StoryPlugin)
+        .add_plugin(SavePlugin)
         .add_plugin(AnimPlugin)
         .add_plugin(EnvironmentInitPlugin)
-        .add_plugin(KeyboardInputPlugin);
+        .add_plugin(KeyboardInputPlugin)
+        .add_plugin(GamepadInputPlugin)
+        .add_plugin(ClickToMovePlugin)
+        .add_plugin(ReplayPlugin);
 
-    app.add_system(move_player)
-        .add_system(player_anim_controller)
-        .add_system(attack_system)
-        .add_system(mouse_walk_anim)
-        .add_system(mouse_idle_anim)
-        .add_system(camera_follow)
-        .add_system(mousey_interact)
-        .add_system(door_interact)
-        .add_system(mouse_trash_animator)
-        .add_system(mouse_door_anim_player)
-        .add_system(flip_flippables)
-        .add_system(flip_interactor)
-        .add_system(interact_col_event_sys)
-        .add_system(interact_events_pt2)
-        .add_system(update_size_on_y)
-        .add_system(auto_sort_on_y)
-        .add_system(reinsert_colliders);
-
-    app.add_event::<InteractEvent>();
+    app.add_plugins(GameplayPlugins);
 
     app.register_type::<YOffset>();
 
     app.run();
 }
 
+/// Everything that runs the game itself, as opposed to menus, input devices and rendering.
+/// Shared with the headless test app.
+struct GameplayPlugins;
+
+impl PluginGroup for GameplayPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(PlayerPlugin)
+            .add(InteractionPlugin)
+            .add(PromptPlugin)
+            .add(DialoguePlugin)
+            .add(RosterPlugin)
+            .add(CompanionPlugin)
+            .add(CutscenePlugin)
+            .add(DepthPlugin)
+            .add(CameraPlugin)
+    }
+}
+
 struct DebugPlugins;
 
 impl PluginGroup for DebugPlugins {
@@ -0,0 +1,456 @@
+use std::marker::PhantomData;
+use bevy::app::AppExit;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use crate::assets::{AppState, FontEnum, GameAssets};
+use crate::keyboard_input::{InputAction, InputMap};
+use crate::save::{latest_save, LoadGame, SaveSlot};
+use crate::settings::GameSettings;
+use crate::ui::full_screen_column;
+
+const BUTTON_COLOR: Color = Color::rgb(0.15, 0.15, 0.15);
+const SELECTED_BUTTON_COLOR: Color = Color::rgb(0.3, 0.45, 0.25);
+const DISABLED_TEXT_COLOR: Color = Color::rgb(0.45, 0.45, 0.45);
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<MenuEvent>()
+            .init_resource::<MenuCursor>()
+            .init_resource::<MenuPage>()
+            .init_resource::<Rebinding>()
+            .add_system(capture_rebind.before(menu_keyboard_nav))
+            .add_system(menu_keyboard_nav)
+            .add_system(menu_mouse_nav)
+            .add_system(settings_menu_actions)
+            .add_system(highlight_menu_items.after(menu_keyboard_nav).after(menu_mouse_nav))
+            .add_system_set(
+                SystemSet::on_enter(AppState::MainMenu)
+                    .with_system(open_main_menu)
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::MainMenu)
+                    .with_system(draw_main_menu)
+                    .with_system(main_menu_actions)
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::MainMenu)
+                    .with_system(close_menu)
+            );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MenuAction {
+    NewGame,
+    Continue,
+    Resume,
+    Save,
+    SaveTo(SaveSlot),
+    Load,
+    LoadFrom(SaveSlot),
+    Settings,
+    ToggleFullscreen,
+    CycleInputDevice,
+    ToggleClickToMove,
+    Controls,
+    Rebind(InputAction),
+    ResetControls,
+    Back,
+    QuitToMenu,
+    Quit,
+}
+
+/// Sent when a menu item is activated, by Enter/Space or by clicking it.
+pub struct MenuEvent(pub MenuAction);
+
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum MenuPage {
+    #[default]
+    Main,
+    Save,
+    Load,
+    Settings,
+    Controls,
+}
+
+impl MenuPage {
+    /// The page `Back` leads to.
+    pub fn parent(&self) -> MenuPage {
+        match self {
+            MenuPage::Controls => MenuPage::Settings,
+            _ => MenuPage::Main,
+        }
+    }
+}
+
+/// Index of the selected item on the current menu page.
+#[derive(Resource, Default)]
+pub struct MenuCursor(pub usize);
+
+/// The action waiting for a key to be pressed on the controls page.
+#[derive(Resource, Default)]
+pub struct Rebinding(pub Option<InputAction>);
+
+/// Everything the settings pages show, so menus can redraw when any of it changes.
+#[derive(SystemParam)]
+pub struct SettingsView<'w, 's> {
+    pub settings: Res<'w, GameSettings>,
+    pub input_map: Res<'w, InputMap>,
+    pub rebinding: Res<'w, Rebinding>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+
+impl SettingsView<'_, '_> {
+    pub fn is_changed(&self) -> bool {
+        self.settings.is_changed() || self.input_map.is_changed() || self.rebinding.is_changed()
+    }
+}
+
+#[derive(Component)]
+pub struct MenuItem {
+    pub index: usize,
+    pub action: MenuAction,
+    pub enabled: bool,
+}
+
+#[derive(Component)]
+pub struct MenuRoot;
+
+#[derive(Component)]
+pub struct MenuCamera;
+
+pub struct MenuEntry {
+    pub action: MenuAction,
+    pub label: String,
+    pub enabled: bool,
+}
+
+impl MenuEntry {
+    pub fn new(action: MenuAction, label: impl Into<String>) -> Self {
+        Self { action, label: label.into(), enabled: true }
+    }
+
+    pub fn disabled(mut self) -> Self {
+        self.enabled = false;
+        self
+    }
+}
+
+pub fn settings_entries(settings: &GameSettings) -> Vec<MenuEntry> {
+    vec![
+        MenuEntry::new(
+            MenuAction::ToggleFullscreen,
+            format!("Fullscreen: {}", if settings.fullscreen { "On" } else { "Off" }),
+        ),
+        MenuEntry::new(MenuAction::CycleInputDevice, format!("Input: {:?}", settings.input_device)),
+        MenuEntry::new(
+            MenuAction::ToggleClickToMove,
+            format!("Click to move: {}", if settings.click_to_move { "On" } else { "Off" }),
+        ),
+        MenuEntry::new(MenuAction::Controls, "Controls"),
+        MenuEntry::new(MenuAction::Back, "Back"),
+    ]
+}
+
+/// One entry per action showing its keys. Activating one waits for the key to bind it to.
+pub fn controls_entries(input_map: &InputMap, rebinding: Option<InputAction>) -> Vec<MenuEntry> {
+    let mut entries: Vec<MenuEntry> = InputAction::ALL.iter()
+        .map(|&action| {
+            let keys = if rebinding == Some(action) {
+                "press a key...".to_string()
+            } else {
+                input_map.keys(action).iter()
+                    .map(|key| format!("{key:?}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            MenuEntry::new(MenuAction::Rebind(action), format!("{}: {keys}", action.label()))
+        })
+        .collect();
+    entries.push(MenuEntry::new(MenuAction::ResetControls, "Reset to defaults"));
+    entries.push(MenuEntry::new(MenuAction::Back, "Back"));
+    entries
+}
+
+/// One entry per save slot. The autosave slot can be loaded from but not saved to.
+pub fn save_slot_entries(loading: bool) -> Vec<MenuEntry> {
+    let mut entries: Vec<MenuEntry> = SaveSlot::all()
+        .filter(|&slot| loading || slot != SaveSlot::Auto)
+        .map(|slot| {
+            let save = slot.read();
+            let label = match &save {
+                Some(save) => format!("{}: {:?}", slot.name(), save.level),
+                None => format!("{}: Empty", slot.name()),
+            };
+            match (loading, save) {
+                (true, Some(_)) => MenuEntry::new(MenuAction::LoadFrom(slot), label),
+                (true, None) => MenuEntry::new(MenuAction::LoadFrom(slot), label).disabled(),
+                (false, _) => MenuEntry::new(MenuAction::SaveTo(slot), label),
+            }
+        })
+        .collect();
+    entries.push(MenuEntry::new(MenuAction::Back, "Back"));
+    entries
+}
+
+pub fn spawn_menu(
+    commands: &mut Commands,
+    assets: &GameAssets,
+    title: &str,
+    entries: &[MenuEntry],
+    background: Color,
+) {
+    let font = assets.font(FontEnum::Ui);
+
+    commands
+        .spawn((full_screen_column(background), MenuRoot))
+        .with_children(|p| {
+            p.spawn(
+                TextBundle::from_section(title, TextStyle {
+                    font: font.clone(),
+                    font_size: 56.,
+                    color: Color::WHITE,
+                })
+                    .with_style(Style {
+                        margin: UiRect::bottom(Val::Px(32.)),
+                        ..default()
+                    }),
+            );
+
+            for (index, entry) in entries.iter().enumerate() {
+                p.spawn((
+                    ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(280.), Val::Px(56.)),
+                            margin: UiRect::all(Val::Px(6.)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: BUTTON_COLOR.into(),
+                        ..default()
+                    },
+                    MenuItem { index, action: entry.action, enabled: entry.enabled },
+                )).with_children(|p| {
+                    p.spawn(TextBundle::from_section(entry.label.clone(), TextStyle {
+                        font: font.clone(),
+                        font_size: 28.,
+                        color: if entry.enabled { Color::WHITE } else { DISABLED_TEXT_COLOR },
+                    }));
+                });
+            }
+        });
+}
+
+/// Spawns `main_entries` under `title`, or one of the shared pages, depending on `page`.
+pub fn spawn_menu_page(
+    commands: &mut Commands,
+    assets: &GameAssets,
+    page: MenuPage,
+    settings: &SettingsView,
+    title: &str,
+    main_entries: &[MenuEntry],
+    background: Color,
+) {
+    match page {
+        MenuPage::Main => spawn_menu(commands, assets, title, main_entries, background),
+        MenuPage::Save => spawn_menu(commands, assets, "Save", &save_slot_entries(false), background),
+        MenuPage::Load => spawn_menu(commands, assets, "Load", &save_slot_entries(true), background),
+        MenuPage::Settings => spawn_menu(
+            commands, assets, "Settings", &settings_entries(&settings.settings), background,
+        ),
+        MenuPage::Controls => spawn_menu(
+            commands,
+            assets,
+            "Controls",
+            &controls_entries(&settings.input_map, settings.rebinding.0),
+            background,
+        ),
+    }
+}
+
+pub fn despawn_menu(commands: &mut Commands, roots: &Query<Entity, With<MenuRoot>>) {
+    for root in roots.iter() {
+        commands.entity(root).despawn_recursive();
+    }
+}
+
+fn menu_keyboard_nav(
+    keys: Res<Input<KeyCode>>,
+    items: Query<&MenuItem>,
+    mut cursor: ResMut<MenuCursor>,
+    mut events: EventWriter<MenuEvent>,
+) {
+    let mut enabled: Vec<&MenuItem> = items.iter().filter(|item| item.enabled).collect();
+    if enabled.is_empty() {
+        return;
+    }
+    enabled.sort_by_key(|item| item.index);
+
+    // The cursor only rests on enabled items. If it isn't on one yet (the page was just drawn with
+    // a disabled item first), it's moved to the next enabled one, and a press this frame waits
+    // until that one is highlighted rather than activating something that wasn't.
+    let Some(mut pos) = enabled.iter().position(|item| item.index == cursor.0)
+        else {
+            let next = enabled.iter().find(|item| item.index > cursor.0).unwrap_or(&enabled[0]);
+            cursor.0 = next.index;
+            return;
+        };
+    if keys.any_just_pressed([KeyCode::Up, KeyCode::W]) {
+        pos = (pos + enabled.len() - 1) % enabled.len();
+    }
+    if keys.any_just_pressed([KeyCode::Down, KeyCode::S]) {
+        pos = (pos + 1) % enabled.len();
+    }
+    if cursor.0 != enabled[pos].index {
+        cursor.0 = enabled[pos].index;
+    }
+
+    if keys.any_just_pressed([KeyCode::Return, KeyCode::Space]) {
+        events.send(MenuEvent(enabled[pos].action));
+    }
+}
+
+fn menu_mouse_nav(
+    items: Query<(&Interaction, &MenuItem), Changed<Interaction>>,
+    mut cursor: ResMut<MenuCursor>,
+    mut events: EventWriter<MenuEvent>,
+) {
+    for (interaction, item) in items.iter() {
+        if !item.enabled {
+            continue;
+        }
+        match interaction {
+            Interaction::Hovered => cursor.0 = item.index,
+            Interaction::Clicked => {
+                cursor.0 = item.index;
+                events.send(MenuEvent(item.action));
+            }
+            Interaction::None => {}
+        }
+    }
+}
+
+fn highlight_menu_items(cursor: Res<MenuCursor>, mut items: Query<(&MenuItem, &mut BackgroundColor)>) {
+    for (item, mut color) in items.iter_mut() {
+        *color = if item.enabled && item.index == cursor.0 {
+            SELECTED_BUTTON_COLOR
+        } else {
+            BUTTON_COLOR
+        }.into();
+    }
+}
+
+fn settings_menu_actions(
+    mut events: EventReader<MenuEvent>,
+    mut page: ResMut<MenuPage>,
+    mut settings: ResMut<GameSettings>,
+    mut input_map: ResMut<InputMap>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    for MenuEvent(action) in events.iter() {
+        if rebinding.0.is_some() {
+            rebinding.0 = None;
+        }
+        match action {
+            MenuAction::ToggleFullscreen => settings.fullscreen = !settings.fullscreen,
+            MenuAction::CycleInputDevice => settings.input_device = settings.input_device.next(),
+            MenuAction::ToggleClickToMove => settings.click_to_move = !settings.click_to_move,
+            MenuAction::Controls => *page = MenuPage::Controls,
+            MenuAction::Rebind(action) => rebinding.0 = Some(*action),
+            MenuAction::ResetControls => *input_map = InputMap::default(),
+            _ => {}
+        }
+    }
+}
+
+// Runs before the menu navigation so the key being bound doesn't also move the cursor.
+// Escape cancels, so it can't be bound from here.
+pub fn capture_rebind(
+    mut keys: ResMut<Input<KeyCode>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut input_map: ResMut<InputMap>,
+) {
+    let Some(action) = rebinding.0 else { return; };
+    let Some(&key) = keys.get_just_pressed().next() else { return; };
+    keys.clear_just_pressed(key);
+    if key != KeyCode::Escape {
+        input_map.rebind(action, key);
+    }
+    rebinding.0 = None;
+}
+
+fn open_main_menu(mut commands: Commands, mut page: ResMut<MenuPage>) {
+    commands.spawn((Camera2dBundle::default(), MenuCamera));
+    *page = MenuPage::Main;
+}
+
+fn draw_main_menu(
+    mut commands: Commands,
+    page: Res<MenuPage>,
+    settings: SettingsView,
+    assets: Res<GameAssets>,
+    mut cursor: ResMut<MenuCursor>,
+    roots: Query<Entity, With<MenuRoot>>,
+) {
+    if !page.is_changed() && !settings.is_changed() {
+        return;
+    }
+    if page.is_changed() {
+        cursor.0 = 0;
+    }
+    despawn_menu(&mut commands, &roots);
+
+    let has_save = latest_save().is_some();
+    let continue_entry = MenuEntry::new(MenuAction::Continue, "Continue");
+    let load_entry = MenuEntry::new(MenuAction::Load, "Load");
+    spawn_menu_page(&mut commands, &assets, *page, &settings, "Tree Game", &[
+        MenuEntry::new(MenuAction::NewGame, "New Game"),
+        if has_save { continue_entry } else { continue_entry.disabled() },
+        if has_save { load_entry } else { load_entry.disabled() },
+        MenuEntry::new(MenuAction::Settings, "Settings"),
+        MenuEntry::new(MenuAction::Quit, "Quit"),
+    ], Color::BLACK);
+}
+
+fn main_menu_actions(
+    mut events: EventReader<MenuEvent>,
+    mut state: ResMut<State<AppState>>,
+    mut page: ResMut<MenuPage>,
+    mut loads: EventWriter<LoadGame>,
+    mut exit: EventWriter<AppExit>,
+) {
+    for MenuEvent(action) in events.iter() {
+        match action {
+            MenuAction::NewGame => {
+                state.set(AppState::InGame).unwrap();
+                return;
+            }
+            MenuAction::Continue => {
+                if let Some(slot) = latest_save() {
+                    loads.send(LoadGame(slot));
+                }
+            }
+            MenuAction::Load => *page = MenuPage::Load,
+            MenuAction::LoadFrom(slot) => loads.send(LoadGame(*slot)),
+            MenuAction::Settings => *page = MenuPage::Settings,
+            MenuAction::Back => *page = page.parent(),
+            MenuAction::Quit => exit.send(AppExit),
+            _ => {}
+        }
+    }
+}
+
+fn close_menu(
+    mut commands: Commands,
+    roots: Query<Entity, With<MenuRoot>>,
+    cameras: Query<Entity, With<MenuCamera>>,
+) {
+    despawn_menu(&mut commands, &roots);
+    for camera in cameras.iter() {
+        commands.entity(camera).despawn();
+    }
+}
@@ -0,0 +1,126 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::RapierConfiguration;
+use crate::assets::{AppState, GameAssets};
+use crate::keyboard_input::{InputAction, InputMap};
+use crate::menu::{capture_rebind, despawn_menu, MenuAction, MenuCursor, MenuEntry, MenuEvent, MenuPage, MenuRoot, SettingsView, spawn_menu_page};
+use crate::save::SaveGame;
+
+const OVERLAY_COLOR: Color = Color::rgba(0., 0., 0., 0.6);
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(AppState::InGame)
+                .with_system(pause_on_key)
+        )
+            .add_system_set(
+                SystemSet::on_enter(AppState::Paused)
+                    .with_system(freeze_game)
+                    .with_system(open_pause_menu)
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::Paused)
+                    .with_system(draw_pause_menu)
+                    .with_system(pause_menu_actions.after(capture_rebind))
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::Paused)
+                    .with_system(unfreeze_game)
+                    .with_system(close_pause_menu)
+            );
+    }
+}
+
+// The pause key is cleared once handled, otherwise the state we switch to would see it too
+// and flip straight back in the same frame. A state change already queued this frame (a door,
+// quitting, a load) wins, and the press is dropped.
+fn pause_on_key(
+    mut keys: ResMut<Input<KeyCode>>,
+    input_map: Res<InputMap>,
+    mut state: ResMut<State<AppState>>,
+) {
+    if input_map.clear_just_pressed(InputAction::Pause, &mut keys) {
+        let _ = state.push(AppState::Paused);
+    }
+}
+
+/// Stops everything driven by `Time` (movement, animators, scripted animations) and the physics step.
+fn freeze_game(mut time: ResMut<Time>, mut rapier: ResMut<RapierConfiguration>) {
+    time.pause();
+    rapier.physics_pipeline_active = false;
+}
+
+fn unfreeze_game(mut time: ResMut<Time>, mut rapier: ResMut<RapierConfiguration>) {
+    time.unpause();
+    rapier.physics_pipeline_active = true;
+}
+
+fn open_pause_menu(mut page: ResMut<MenuPage>) {
+    *page = MenuPage::Main;
+}
+
+fn draw_pause_menu(
+    mut commands: Commands,
+    page: Res<MenuPage>,
+    settings: SettingsView,
+    assets: Res<GameAssets>,
+    mut cursor: ResMut<MenuCursor>,
+    roots: Query<Entity, With<MenuRoot>>,
+) {
+    if !page.is_changed() && !settings.is_changed() {
+        return;
+    }
+    if page.is_changed() {
+        cursor.0 = 0;
+    }
+    despawn_menu(&mut commands, &roots);
+
+    spawn_menu_page(&mut commands, &assets, *page, &settings, "Paused", &[
+        MenuEntry::new(MenuAction::Resume, "Resume"),
+        MenuEntry::new(MenuAction::Save, "Save"),
+        MenuEntry::new(MenuAction::Settings, "Settings"),
+        MenuEntry::new(MenuAction::QuitToMenu, "Quit to menu"),
+    ], OVERLAY_COLOR);
+}
+
+fn pause_menu_actions(
+    mut events: EventReader<MenuEvent>,
+    mut keys: ResMut<Input<KeyCode>>,
+    input_map: Res<InputMap>,
+    mut state: ResMut<State<AppState>>,
+    mut page: ResMut<MenuPage>,
+    mut saves: EventWriter<SaveGame>,
+) {
+    // As with pausing, whichever state change was queued first this frame wins.
+    if input_map.clear_just_pressed(InputAction::Pause, &mut keys) {
+        let _ = state.pop();
+        return;
+    }
+
+    for MenuEvent(action) in events.iter() {
+        match action {
+            MenuAction::Resume => {
+                let _ = state.pop();
+                return;
+            }
+            MenuAction::QuitToMenu => {
+                let _ = state.replace(AppState::MainMenu);
+                return;
+            }
+            MenuAction::Save => *page = MenuPage::Save,
+            MenuAction::SaveTo(slot) => {
+                saves.send(SaveGame(*slot));
+                *page = MenuPage::Main;
+            }
+            MenuAction::Settings => *page = MenuPage::Settings,
+            MenuAction::Back => *page = page.parent(),
+            _ => {}
+        }
+    }
+}
+
+fn close_pause_menu(mut commands: Commands, roots: Query<Entity, With<MenuRoot>>) {
+    despawn_menu(&mut commands, &roots);
+}
@@ -0,0 +1,107 @@
+use bevy::prelude::*;
+use crate::assets::{AppState, FontEnum, GameAssets};
+use crate::dialogue::ActiveDialogue;
+use crate::gamepad_input::ActiveDevice;
+use crate::init_systems::LevelState;
+use crate::init_systems::level::LevelEntity;
+use crate::interaction::{Interactable, Interacting};
+use crate::keyboard_input::{InputAction, InputMap};
+use crate::player::Player;
+use crate::schedule::GameSystems;
+use crate::transition::LevelTransition;
+
+/// How far above the interactable's centre the prompt floats.
+const PROMPT_HEIGHT: f32 = 60.;
+/// In front of everything sorted by `AutoSortOnY`, behind the camera.
+const PROMPT_LAYER: f32 = 50.;
+const PROMPT_FONT_SIZE: f32 = 18.;
+
+/// Shows "[E] Go inside" over the interactable the player would use by pressing interact.
+pub struct PromptPlugin;
+
+impl Plugin for PromptPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(AppState::InGame)
+                .label(GameSystems::Camera)
+                .after(GameSystems::Animation)
+                .with_system(show_interact_prompt)
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct InteractPrompt {
+    target: Entity,
+}
+
+/// The interact button on the device the player is using.
+fn button_icon(device: ActiveDevice, input_map: &InputMap) -> String {
+    match device {
+        // The south face button, labelled A on most pads.
+        ActiveDevice::Gamepad(_) => "(A)".to_string(),
+        ActiveDevice::Keyboard => match input_map.keys(InputAction::Interact).first() {
+            Some(key) => format!("[{key:?}]"),
+            None => "[?]".to_string(),
+        },
+    }
+}
+
+fn prompt_position(target: &GlobalTransform) -> Vec3 {
+    target.translation().truncate().extend(0.) + Vec3::new(0., PROMPT_HEIGHT, PROMPT_LAYER)
+}
+
+fn show_interact_prompt(
+    mut commands: Commands,
+    players: Query<&Interacting, With<Player>>,
+    interactables: Query<(&Interactable, &GlobalTransform)>,
+    mut prompts: Query<(Entity, &InteractPrompt, &mut Transform, &mut Text)>,
+    transition: Res<LevelTransition>,
+    device: Res<ActiveDevice>,
+    input_map: Res<InputMap>,
+    assets: Res<GameAssets>,
+    level: Res<State<LevelState>>,
+    dialogue: Option<Res<ActiveDialogue>>,
+) {
+    let target = match players.get_single() {
+        Ok(interacting) if !transition.is_running() && dialogue.is_none() => interactables.get(interacting.entity).ok()
+            .map(|(interactable, trans)| (interacting.entity, interactable, trans)),
+        _ => None,
+    };
+
+    let mut shown = false;
+    for (entity, prompt, mut trans, mut text) in prompts.iter_mut() {
+        match target {
+            Some((target, interactable, target_trans)) if prompt.target == target && !shown => {
+                trans.translation = prompt_position(target_trans);
+                let label = format!("{} {}", button_icon(*device, &input_map), interactable.prompt);
+                if text.sections[0].value != label {
+                    text.sections[0].value = label;
+                }
+                shown = true;
+            }
+            _ => commands.entity(entity).despawn(),
+        }
+    }
+
+    let Some((target, interactable, target_trans)) = target else { return; };
+    if shown {
+        return;
+    }
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                format!("{} {}", button_icon(*device, &input_map), interactable.prompt),
+                TextStyle {
+                    font: assets.font(FontEnum::Ui),
+                    font_size: PROMPT_FONT_SIZE,
+                    color: Color::WHITE,
+                },
+            ).with_alignment(TextAlignment::CENTER),
+            transform: Transform::from_translation(prompt_position(target_trans)),
+            ..default()
+        },
+        InteractPrompt { target },
+        LevelEntity(*level.current()),
+    ));
+}
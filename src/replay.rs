@@ -0,0 +1,396 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use bevy::app::AppExit;
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use bevy::ui::UiSystem;
+use serde::{Deserialize, Serialize};
+use crate::assets::AppState;
+use crate::gamepad_input::gamepad_input;
+use crate::init_systems::LevelState;
+use crate::keyboard_input::{keyboard_input, mouse_buttons, mouse_position, PlayerInput};
+use crate::save::{continue_from, LoadedSave, SaveData};
+use crate::schedule::GameSystems;
+use crate::settings::GameSettings;
+use crate::story::StoryFlags;
+
+/// Records the player's input with `--record <file>`, and plays it back with `--replay <file>`.
+///
+/// Both run the game at `FIXED_STEP` a frame, however long frames really take. A replay starts the
+/// same game the recording did, new or loaded, straight from the main menu and gives every frame
+/// the input it had when recorded, so the game plays out the same way.
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(InputReplay::from_args())
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .with_system(
+                        drive_player_input
+                            .label(GameSystems::Input)
+                            .after(keyboard_input)
+                            .after(mouse_position)
+                            .after(mouse_buttons)
+                            .after(gamepad_input)
+                    )
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::MainMenu)
+                    .with_system(start_replay)
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::InGame)
+                    .with_system(start_recording)
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::InGame)
+                    .with_system(write_recording)
+            )
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                drop_live_input.after(InputSystem).before(UiSystem::Focus),
+            )
+            .add_system_to_stage(CoreStage::Last, write_recording_on_exit)
+            .add_system_to_stage(CoreStage::Last, schedule_fixed_step);
+    }
+}
+
+/// How much game time passes each frame while recording or replaying.
+pub const FIXED_STEP: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+/// One frame of `PlayerInput`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct InputFrame {
+    pub movement: Vec2,
+    pub mouse_pos: Vec2,
+    #[serde(default)]
+    pub just_clicked: bool,
+    #[serde(default)]
+    pub just_interacted: bool,
+    #[serde(default)]
+    pub just_attacked: bool,
+    #[serde(default)]
+    pub just_switched: bool,
+}
+
+impl InputFrame {
+    fn new(input: &PlayerInput) -> Self {
+        Self {
+            movement: input.movement,
+            mouse_pos: input.mouse_pos,
+            just_clicked: input.just_clicked,
+            just_interacted: input.just_interacted,
+            just_attacked: input.just_attacked,
+            just_switched: input.just_switched,
+        }
+    }
+
+    fn apply(&self, input: &mut PlayerInput) {
+        input.movement = self.movement;
+        input.mouse_pos = self.mouse_pos;
+        input.just_clicked = self.just_clicked;
+        input.just_interacted = self.just_interacted;
+        input.just_attacked = self.just_attacked;
+        input.just_switched = self.just_switched;
+    }
+}
+
+/// A recorded session. The settings are kept too, since click-to-move changes what a click does.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct InputRecording {
+    pub settings: GameSettings,
+    /// The save the session was continued from, or `None` if it was a new game.
+    #[serde(default)]
+    pub start: Option<SaveData>,
+    pub frames: Vec<InputFrame>,
+}
+
+impl InputRecording {
+    pub fn read(path: &PathBuf) -> Option<Self> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) => {
+                error!("could not read replay {path:?}: {err}");
+                return None;
+            }
+        };
+        match ron::from_str(&text) {
+            Ok(recording) => Some(recording),
+            Err(err) => {
+                error!("could not parse replay {path:?}: {err}");
+                None
+            }
+        }
+    }
+
+    pub fn write(&self, path: &PathBuf) {
+        let result = ron::ser::to_string_pretty(self, default())
+            .map_err(|err| err.to_string())
+            .and_then(|text| fs::write(path, text).map_err(|err| err.to_string()));
+        match result {
+            Ok(()) => info!("recorded {} frames to {path:?}", self.frames.len()),
+            Err(err) => error!("could not write replay {path:?}: {err}"),
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub enum InputReplay {
+    #[default]
+    Off,
+    Recording {
+        path: PathBuf,
+        recording: InputRecording,
+    },
+    Playing {
+        recording: InputRecording,
+        /// Index of the frame to play next.
+        next: usize,
+        started: bool,
+    },
+}
+
+impl InputReplay {
+    fn from_args() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+        let value_of = |flag: &str| {
+            args.iter()
+                .position(|arg| arg == flag)
+                .and_then(|i| args.get(i + 1))
+                .map(PathBuf::from)
+        };
+
+        if let Some(path) = value_of("--replay") {
+            return match InputRecording::read(&path) {
+                Some(recording) => InputReplay::Playing { recording, next: 0, started: false },
+                None => InputReplay::Off,
+            };
+        }
+        if let Some(path) = value_of("--record") {
+            return InputReplay::Recording {
+                path,
+                recording: InputRecording { settings: default(), start: None, frames: vec![] },
+            };
+        }
+        InputReplay::Off
+    }
+}
+
+fn start_replay(
+    mut commands: Commands,
+    mut replay: ResMut<InputReplay>,
+    mut settings: ResMut<GameSettings>,
+    mut level: ResMut<State<LevelState>>,
+    mut flags: ResMut<StoryFlags>,
+    mut state: ResMut<State<AppState>>,
+) {
+    let InputReplay::Playing { recording, started, .. } = &mut *replay else { return; };
+    if *started {
+        return;
+    }
+    *started = true;
+    *settings = recording.settings;
+    match recording.start.clone() {
+        // Loaded the way the menu would, so the same level, characters and story are there to play into.
+        Some(data) => continue_from(&mut commands, data, &mut level, &mut flags),
+        None => state.set(AppState::InGame).unwrap(),
+    }
+}
+
+// Each game gets its own recording, so quitting to the menu and starting over keeps only the last.
+fn start_recording(
+    mut replay: ResMut<InputReplay>,
+    settings: Res<GameSettings>,
+    save: Option<Res<LoadedSave>>,
+) {
+    if let InputReplay::Recording { recording, .. } = &mut *replay {
+        recording.settings = *settings;
+        recording.start = save.map(|save| save.0.clone());
+        recording.frames.clear();
+    }
+}
+
+/// Forgets the real keys and buttons while a replay plays, so nothing reading them directly (the
+/// pause key, the menus, a focus loss releasing keys) can step in between the recorded frames.
+fn drop_live_input(
+    replay: Res<InputReplay>,
+    mut keys: ResMut<Input<KeyCode>>,
+    mut buttons: ResMut<Input<MouseButton>>,
+    mut gamepad_buttons: ResMut<Input<GamepadButton>>,
+) {
+    if let InputReplay::Playing { .. } = *replay {
+        keys.reset_all();
+        buttons.reset_all();
+        gamepad_buttons.reset_all();
+    }
+}
+
+/// Records `PlayerInput` once every device has written to it, or overwrites it with the replay.
+fn drive_player_input(
+    mut replay: ResMut<InputReplay>,
+    mut input: ResMut<PlayerInput>,
+) {
+    let finished = match &mut *replay {
+        InputReplay::Off => false,
+        InputReplay::Recording { recording, .. } => {
+            recording.frames.push(InputFrame::new(&input));
+            false
+        }
+        InputReplay::Playing { recording, next, .. } => {
+            if let Some(frame) = recording.frames.get(*next) {
+                frame.apply(&mut input);
+                *next += 1;
+                false
+            } else {
+                info!("replay finished after {} frames", recording.frames.len());
+                true
+            }
+        }
+    };
+    if finished {
+        *replay = InputReplay::Off;
+    }
+}
+
+// `Time` is updated at the very start of the next frame, so its frame time is set here.
+fn schedule_fixed_step(
+    replay: Res<InputReplay>,
+    time: Res<Time>,
+    mut strategy: ResMut<TimeUpdateStrategy>,
+) {
+    if let InputReplay::Off = *replay {
+        if replay.is_changed() {
+            *strategy = TimeUpdateStrategy::Automatic;
+        }
+        return;
+    }
+    let Some(last_update) = time.last_update() else { return; };
+    *strategy = TimeUpdateStrategy::ManualInstant(last_update + FIXED_STEP);
+}
+
+fn write_recording(replay: Res<InputReplay>) {
+    if let InputReplay::Recording { path, recording } = &*replay {
+        recording.write(path);
+    }
+}
+
+fn write_recording_on_exit(
+    exits: EventReader<AppExit>,
+    replay: Res<InputReplay>,
+    state: Res<State<AppState>>,
+) {
+    if !exits.is_empty() && *state.current() == AppState::InGame {
+        write_recording(replay);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use bevy::input::InputPlugin;
+    use bevy::prelude::*;
+    use crate::assets::AppState;
+    use crate::click_to_move::feet;
+    use crate::init_systems::{LevelState, Mousey, YOffset};
+    use crate::interaction::{InteractAction, InteractEvent, Interactable, Interacting};
+    use crate::player::{Player, Trunk};
+    use crate::save::Character;
+    use crate::settings::GameSettings;
+    use crate::test_app::TestApp;
+    use super::{InputRecording, InputReplay, ReplayPlugin};
+
+    /// Every interaction and every level entered, numbered by the in-game frame it was seen on.
+    #[derive(Resource, Default)]
+    struct Happenings(Vec<(usize, String)>);
+
+    fn note_happenings(
+        mut frame: Local<usize>,
+        mut last_level: Local<Option<LevelState>>,
+        mut events: EventReader<InteractEvent>,
+        interactables: Query<&Interactable>,
+        app_state: Res<State<AppState>>,
+        level: Res<State<LevelState>>,
+        mut happenings: ResMut<Happenings>,
+    ) {
+        if *app_state.current() != AppState::InGame {
+            events.clear();
+            return;
+        }
+        *frame += 1;
+        for event in events.iter() {
+            let action = interactables.get(event.interactable).map_or(String::new(), |i| format!("{:?}", i.action));
+            happenings.0.push((*frame, action));
+        }
+        if *last_level != Some(*level.current()) {
+            *last_level = Some(*level.current());
+            happenings.0.push((*frame, format!("{:?}", level.current())));
+        }
+    }
+
+    fn app_with(replay: InputReplay) -> TestApp {
+        let mut app = TestApp::new();
+        app.0.add_plugin(InputPlugin)
+            .add_plugin(ReplayPlugin)
+            .insert_resource(replay)
+            .init_resource::<GameSettings>()
+            .init_resource::<Happenings>()
+            .add_system_to_stage(CoreStage::PostUpdate, note_happenings);
+        app
+    }
+
+    #[test]
+    fn a_replay_frees_mousey_and_goes_through_the_door_like_the_recording() {
+        let recording = InputRecording { settings: default(), start: None, frames: vec![] };
+        let mut app = app_with(InputReplay::Recording { path: PathBuf::new(), recording });
+        // Next to the trash can, as in the test of freeing Mousey.
+        app.start_as(LevelState::HouseFront, Character::Trunk, (525., -52.), default());
+        let trunk = app.single::<With<Trunk>>();
+        let mousey = app.single::<With<Mousey>>();
+
+        app.step_until(30, "Trunk reaching Mousey", |world| world.get::<Interacting>(trunk).is_some());
+        app.press_interact();
+        app.step_until(240, "Mousey climbing out", |world| world.get::<Player>(mousey).is_some());
+
+        // Walk Mousey over to the front door with the movement input alone, so the replay can too.
+        for _ in 0..300 {
+            let at_door = app.0.world.get::<Interacting>(mousey)
+                .and_then(|interacting| app.0.world.get::<Interactable>(interacting.entity))
+                .map_or(false, |interactable| matches!(interactable.action, InteractAction::Door(_)));
+            if at_door {
+                break;
+            }
+            let pos = feet(app.0.world.get::<Transform>(mousey).unwrap(), app.0.world.get::<YOffset>(mousey));
+            app.input().movement = (Vec2::new(347., -105.) - pos).normalize_or_zero();
+            app.step();
+        }
+        app.input().movement = Vec2::ZERO;
+        app.go_through_door(mousey, LevelState::HouseInside);
+
+        let InputReplay::Recording { recording, .. } = app.0.world.remove_resource::<InputReplay>().unwrap()
+            else { panic!("the recording stopped early"); };
+        let recorded = app.0.world.remove_resource::<Happenings>().unwrap().0;
+        assert_eq!(recorded.iter().filter(|(_, what)| what.starts_with("Door")).count(), 1);
+        assert_eq!(recorded.last().unwrap().1, "HouseInside");
+
+        let frames = recording.frames.len();
+        let mut replay = app_with(InputReplay::Playing { recording, next: 0, started: false });
+        // The replay loads the recording's save from the menu; entering the game is left to the
+        // save plugin, which the test app doesn't have.
+        replay.step();
+        replay.start_game(None);
+        replay.step_until(frames + 10, "the replay finishing", |world| matches!(world.resource::<InputReplay>(), InputReplay::Off));
+        assert_eq!(replay.0.world.resource::<Happenings>().0, recorded);
+    }
+
+    #[test]
+    fn live_keys_are_ignored_while_replaying() {
+        let recording = InputRecording { settings: default(), start: None, frames: vec![] };
+        let mut app = app_with(InputReplay::Playing { recording, next: 0, started: false });
+        app.0.world.resource_mut::<Input<KeyCode>>().press(KeyCode::Escape);
+        app.step();
+        assert!(!app.0.world.resource::<Input<KeyCode>>().pressed(KeyCode::Escape));
+    }
+}
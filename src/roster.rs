@@ -0,0 +1,132 @@
+use bevy::prelude::*;
+use crate::assets::AppState;
+use crate::camera::CameraTarget;
+use crate::click_to_move::MoveTarget;
+use crate::dialogue::ActiveDialogue;
+use crate::interaction::Interacting;
+use crate::keyboard_input::PlayerInput;
+use crate::player::Player;
+use crate::save::Character;
+use crate::schedule::GameSystems;
+use crate::story::{StoryFlag, StoryFlags};
+use crate::transition::LevelTransition;
+
+/// Everyone the player can control, in the order switching goes through them.
+pub const ROSTER: [Character; 2] = [Character::Trunk, Character::Mousey];
+
+/// Lets the player swap between the characters they've unlocked, when there's more than one around.
+pub struct RosterPlugin;
+
+impl Plugin for RosterPlugin {
+    fn build(&self, app: &mut App) {
+        // Decides who the input is for, so it goes before anything moves.
+        app.add_system_set(
+            SystemSet::on_update(AppState::InGame)
+                .after(GameSystems::Input)
+                .before(GameSystems::Movement)
+                .with_system(switch_character)
+        );
+    }
+}
+
+/// A character the player can take control of, once `unlocked`.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct ControllableCharacter(pub Character);
+
+pub fn unlocked(character: Character, flags: &StoryFlags) -> bool {
+    match character {
+        Character::Trunk => true,
+        Character::Mousey => flags.is_set(StoryFlag::MouseyFreed),
+    }
+}
+
+/// Hands control to the next unlocked character in the level. Nobody can be switched to or from
+/// while they're busy with something that took `Player` away, like going through a door.
+fn switch_character(
+    mut commands: Commands,
+    input: Res<PlayerInput>,
+    flags: Res<StoryFlags>,
+    transition: Res<LevelTransition>,
+    dialogue: Option<Res<ActiveDialogue>>,
+    players: Query<(Entity, &ControllableCharacter), With<Player>>,
+    others: Query<(Entity, &ControllableCharacter), Without<Player>>,
+) {
+    if !input.just_switched || transition.is_running() || dialogue.is_some() {
+        return;
+    }
+    let Ok((current, &ControllableCharacter(from))) = players.get_single() else { return; };
+
+    let next = ROSTER.iter()
+        .cycle()
+        .skip_while(|&&character| character != from)
+        .skip(1)
+        .take(ROSTER.len() - 1)
+        .filter(|&&character| unlocked(character, &flags))
+        .find_map(|&character| others.iter().find(|(_, other)| other.0 == character));
+    let Some((next, _)) = next else { return; };
+
+    commands.entity(current).remove::<(Player, CameraTarget, Interacting, MoveTarget)>();
+    commands.entity(next).insert((Player, CameraTarget));
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use crate::camera::CameraTarget;
+    use crate::init_systems::{LevelState, Mousey};
+    use crate::player::{Player, Trunk};
+    use crate::save::Character;
+    use crate::test_app::{mousey_freed, saved_as, TestApp};
+
+    fn press_switch(app: &mut TestApp) {
+        app.input().just_switched = true;
+        app.step();
+        app.input().just_switched = false;
+    }
+
+    #[test]
+    fn switching_hands_control_back_and_forth() {
+        let mut app = TestApp::new();
+        app.start_as(LevelState::HouseFront, Character::Mousey, (0., -150.), mousey_freed());
+        let mousey = app.single::<With<Mousey>>();
+        let trunk = app.single::<With<Trunk>>();
+        assert!(app.has::<Player>(mousey) && !app.has::<Player>(trunk));
+
+        press_switch(&mut app);
+        assert!(app.has::<Player>(trunk) && app.has::<CameraTarget>(trunk));
+        assert!(!app.has::<Player>(mousey) && !app.has::<CameraTarget>(mousey));
+
+        press_switch(&mut app);
+        assert!(app.has::<Player>(mousey) && app.has::<CameraTarget>(mousey));
+        assert!(!app.has::<Player>(trunk));
+    }
+
+    #[test]
+    fn switching_still_works_after_going_through_doors() {
+        let mut app = TestApp::new();
+        let mut save = saved_as(LevelState::HouseFront, Character::Mousey, (360., -140.), mousey_freed());
+        save.0.places.set(Character::Trunk, LevelState::HouseFront, Vec2::new(320., -80.));
+        app.start_game(Some(save));
+        let mousey = app.single::<With<Mousey>>();
+        let trunk = app.single::<With<Trunk>>();
+
+        app.go_through_door(mousey, LevelState::HouseInside);
+        press_switch(&mut app);
+        assert!(app.has::<Player>(trunk) && !app.has::<Player>(mousey));
+
+        // Trunk landed on the spawn point by the door, so he can take Mousey back out.
+        app.go_through_door(trunk, LevelState::HouseFront);
+        press_switch(&mut app);
+        assert!(app.has::<Player>(mousey) && !app.has::<Player>(trunk));
+    }
+
+    #[test]
+    fn mousey_is_locked_until_freed() {
+        let mut app = TestApp::new();
+        app.start_game(None);
+        let trunk = app.single::<With<Trunk>>();
+
+        press_switch(&mut app);
+        assert!(app.has::<Player>(trunk));
+    }
+}
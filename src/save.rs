@@ -0,0 +1,264 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::assets::AppState;
+use crate::init_systems::{CharacterPlaces, LevelState, Mousey};
+use crate::init_systems::level::LevelEntity;
+use crate::player::Player;
+use crate::roster::ControllableCharacter;
+use crate::settings::{GameSettings, project_dirs};
+use crate::story::StoryFlags;
+use crate::transition::DoorArrival;
+
+pub const MANUAL_SLOTS: usize = 3;
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SaveGame>()
+            .add_event::<LoadGame>()
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .with_system(autosave_on_arrival)
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::Paused)
+                    .with_system(save_game)
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::MainMenu)
+                    .with_system(load_game)
+                    .with_system(enter_loaded_game.after(load_game))
+            );
+    }
+}
+
+/// The character the player was controlling.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Character {
+    Trunk,
+    Mousey,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SaveData {
+    /// Seconds since the Unix epoch, used to find the latest save.
+    pub saved_at: u64,
+    pub level: LevelState,
+    pub character: Character,
+    pub position: (f32, f32),
+    #[serde(default)]
+    pub flags: StoryFlags,
+    /// Where everyone was, the character not being controlled included.
+    #[serde(default)]
+    pub places: CharacterPlaces,
+    pub settings: GameSettings,
+}
+
+impl SaveData {
+    fn new(
+        level: LevelState,
+        mousey: Option<&Mousey>,
+        position: Vec2,
+        places: CharacterPlaces,
+        flags: &StoryFlags,
+        settings: &GameSettings,
+    ) -> Self {
+        Self {
+            saved_at: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()),
+            level,
+            character: if mousey.is_some() { Character::Mousey } else { Character::Trunk },
+            position: position.into(),
+            flags: flags.clone(),
+            places,
+            settings: *settings,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SaveSlot {
+    /// Written on every level transition.
+    Auto,
+    Manual(usize),
+}
+
+impl SaveSlot {
+    pub fn all() -> impl Iterator<Item = SaveSlot> {
+        std::iter::once(SaveSlot::Auto).chain((0..MANUAL_SLOTS).map(SaveSlot::Manual))
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            SaveSlot::Auto => "Autosave".to_string(),
+            SaveSlot::Manual(i) => format!("Slot {}", i + 1),
+        }
+    }
+
+    fn path(&self) -> PathBuf {
+        let file = match self {
+            SaveSlot::Auto => "autosave.ron".to_string(),
+            SaveSlot::Manual(i) => format!("slot{}.ron", i + 1),
+        };
+        save_dir().join(file)
+    }
+
+    pub fn read(&self) -> Option<SaveData> {
+        let text = fs::read_to_string(self.path()).ok()?;
+        match ron::from_str(&text) {
+            Ok(data) => Some(data),
+            Err(err) => {
+                warn!("ignoring unreadable save {:?}: {err}", self.path());
+                None
+            }
+        }
+    }
+
+    pub fn write(&self, data: &SaveData) {
+        let path = self.path();
+        let result = ron::ser::to_string_pretty(data, default())
+            .map_err(|err| err.to_string())
+            .and_then(|text| {
+                fs::create_dir_all(save_dir()).map_err(|err| err.to_string())?;
+                fs::write(&path, text).map_err(|err| err.to_string())
+            });
+        match result {
+            Ok(()) => info!("saved to {path:?}"),
+            Err(err) => error!("could not save to {path:?}: {err}"),
+        }
+    }
+}
+
+/// `$XDG_DATA_HOME/tree-game/saves` on Linux, or the platform's equivalent.
+fn save_dir() -> PathBuf {
+    match project_dirs() {
+        Some(dirs) => dirs.data_dir().join("saves"),
+        None => PathBuf::from("saves"),
+    }
+}
+
+/// The slot holding the most recent save, if there is any.
+pub fn latest_save() -> Option<SaveSlot> {
+    SaveSlot::all()
+        .filter_map(|slot| slot.read().map(|data| (slot, data.saved_at)))
+        .max_by_key(|&(_, saved_at)| saved_at)
+        .map(|(slot, _)| slot)
+}
+
+pub struct SaveGame(pub SaveSlot);
+
+pub struct LoadGame(pub SaveSlot);
+
+/// The save the current game was started from. Character spawning reads it in place of the
+/// new-game defaults.
+#[derive(Resource)]
+pub struct LoadedSave(pub SaveData);
+
+// Saves the spawn point rather than where the character is, which for Mousey is partway through
+// hopping out of the door.
+fn autosave_on_arrival(
+    mut arrivals: EventReader<DoorArrival>,
+    characters: Query<Option<&Mousey>>,
+    places: Res<CharacterPlaces>,
+    here: Query<(&ControllableCharacter, &LevelEntity, &Transform)>,
+    level: Res<State<LevelState>>,
+    flags: Res<StoryFlags>,
+    settings: Res<GameSettings>,
+) {
+    for DoorArrival(entity, position) in arrivals.iter() {
+        let Ok(mousey) = characters.get(*entity) else { continue; };
+        let mut places = places.clone();
+        places.update(here.iter());
+        SaveSlot::Auto.write(&SaveData::new(*level.current(), mousey, *position, places, &flags, &settings));
+    }
+}
+
+fn save_game(
+    mut events: EventReader<SaveGame>,
+    player: Query<(&Transform, Option<&Mousey>), With<Player>>,
+    places: Res<CharacterPlaces>,
+    here: Query<(&ControllableCharacter, &LevelEntity, &Transform)>,
+    level: Res<State<LevelState>>,
+    flags: Res<StoryFlags>,
+    settings: Res<GameSettings>,
+) {
+    for SaveGame(slot) in events.iter() {
+        let Ok((trans, mousey)) = player.get_single()
+            else {
+                warn!("can't save while no character is being controlled");
+                continue;
+            };
+        let mut places = places.clone();
+        places.update(here.iter());
+        slot.write(&SaveData::new(*level.current(), mousey, trans.translation.truncate(), places, &flags, &settings));
+    }
+}
+
+fn load_game(
+    mut commands: Commands,
+    mut events: EventReader<LoadGame>,
+    mut level: ResMut<State<LevelState>>,
+    mut flags: ResMut<StoryFlags>,
+    mut settings: ResMut<GameSettings>,
+) {
+    for LoadGame(slot) in events.iter() {
+        let Some(data) = slot.read()
+            else {
+                warn!("nothing to load in {}", slot.name());
+                continue;
+            };
+        *settings = data.settings;
+        continue_from(&mut commands, data, &mut level, &mut flags);
+        return;
+    }
+}
+
+/// Gets ready to continue the game `data` was saved from, which `enter_loaded_game` then starts.
+pub fn continue_from(commands: &mut Commands, data: SaveData, level: &mut State<LevelState>, flags: &mut StoryFlags) {
+    *flags = data.flags.clone();
+    if *level.current() != data.level {
+        level.set(data.level).unwrap();
+    }
+    commands.insert_resource(LoadedSave(data));
+}
+
+// The level is switched while still in the menu, so that entering the game spawns the saved one.
+fn enter_loaded_game(
+    save: Option<Res<LoadedSave>>,
+    level: Res<State<LevelState>>,
+    mut state: ResMut<State<AppState>>,
+) {
+    let Some(save) = save else { return; };
+    if *level.current() == save.0.level {
+        state.set(AppState::InGame).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use crate::init_systems::{LevelState, Mousey};
+    use crate::init_systems::level::LevelEntity;
+    use crate::player::Trunk;
+    use crate::test_app::{mousey_freed, saved_as, TestApp};
+    use super::Character;
+
+    #[test]
+    fn loading_puts_the_other_character_back_where_they_were_left() {
+        let mut app = TestApp::new();
+        let mut save = saved_as(LevelState::HouseFront, Character::Mousey, (360., -140.), mousey_freed());
+        save.0.places.set(Character::Trunk, LevelState::HouseInside, Vec2::new(-300., -180.));
+        app.start_game(Some(save));
+        assert_eq!(app.0.world.query_filtered::<Entity, With<Trunk>>().iter(&app.0.world).count(), 0);
+
+        let mousey = app.single::<With<Mousey>>();
+        app.go_through_door(mousey, LevelState::HouseInside);
+        let trunk = app.single::<With<Trunk>>();
+        assert_eq!(app.0.world.get::<LevelEntity>(trunk).unwrap().0, LevelState::HouseInside);
+        let position = app.0.world.get::<Transform>(trunk).unwrap().translation.truncate();
+        assert!(position.distance(Vec2::new(-300., -180.)) < 1.);
+    }
+}
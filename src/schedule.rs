@@ -0,0 +1,15 @@
+use bevy::prelude::SystemLabel;
+
+/// The phases of a gameplay frame, in the order they run. Input is read from devices (or a replay)
+/// into `PlayerInput`, characters move, interactions are resolved, animations are picked for the
+/// result, and finally the camera catches up with wherever the player ended up.
+///
+/// Gameplay plugins add their systems as `AppState::InGame` sets with one of these labels.
+#[derive(SystemLabel, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum GameSystems {
+    Input,
+    Movement,
+    Interaction,
+    Animation,
+    Camera,
+}
@@ -0,0 +1,47 @@
+use bevy::prelude::*;
+use bevy::window::WindowMode;
+use directories::ProjectDirs;
+use crate::gamepad_input::InputDevice;
+use serde::{Deserialize, Serialize};
+
+#[derive(Resource, Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct GameSettings {
+    pub fullscreen: bool,
+    #[serde(default)]
+    pub input_device: InputDevice,
+    /// Walk to where the mouse is clicked.
+    #[serde(default)]
+    pub click_to_move: bool,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self { fullscreen: true, input_device: InputDevice::Auto, click_to_move: false }
+    }
+}
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameSettings>()
+            .add_system(apply_window_mode);
+    }
+}
+
+fn apply_window_mode(settings: Res<GameSettings>, mut windows: ResMut<Windows>) {
+    if !settings.is_changed() {
+        return;
+    }
+    let Some(window) = windows.get_primary_mut() else { return; };
+    window.set_mode(if settings.fullscreen {
+        WindowMode::BorderlessFullscreen
+    } else {
+        WindowMode::Windowed
+    });
+}
+
+/// Where the game keeps its files: `~/.config/tree-game` and `~/.local/share/tree-game` on Linux.
+pub fn project_dirs() -> Option<ProjectDirs> {
+    ProjectDirs::from("", "", "tree-game")
+}
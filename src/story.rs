@@ -0,0 +1,157 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub struct StoryPlugin;
+
+impl Plugin for StoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StoryFlags>()
+            .register_type::<StoryFlags>()
+            .register_type::<StoryFlag>()
+            .register_type::<StoryCounter>()
+            .add_event::<StoryChanged>()
+            // After everything that could have changed the flags this frame, whatever the state.
+            .add_system_to_stage(CoreStage::PostUpdate, send_story_events);
+    }
+}
+
+/// Something that has happened in the story and should stay happened.
+#[derive(Reflect, FromReflect, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum StoryFlag {
+    /// Trunk knocked over the trash can and Mousey came out.
+    MouseyFreed,
+    /// Somebody has talked to the girl in the living room.
+    MetGirl,
+    /// Mousey has talked to the bird in the back yard.
+    MetBird,
+}
+
+/// Something in the story that can happen more than once.
+#[derive(Reflect, FromReflect, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum StoryCounter {
+    /// Conversations with the girl after the first.
+    GirlChats,
+}
+
+/// A requirement on the story so far, for data files to gate things on.
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub enum StoryCondition {
+    Set(StoryFlag),
+    NotSet(StoryFlag),
+    /// The counter has reached at least this much.
+    AtLeast(StoryCounter, u32),
+    /// The counter hasn't reached this much yet.
+    Below(StoryCounter, u32),
+}
+
+/// The story so far, saved with the game. Shown in the world inspector, where it can be edited to
+/// skip ahead.
+#[derive(Resource, Reflect, Serialize, Deserialize, Clone, Default, PartialEq, Debug)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct StoryFlags {
+    flags: Vec<StoryFlag>,
+    counters: Vec<(StoryCounter, u32)>,
+}
+
+impl StoryFlags {
+    pub fn is_set(&self, flag: StoryFlag) -> bool {
+        self.flags.contains(&flag)
+    }
+
+    pub fn set(&mut self, flag: StoryFlag) {
+        if !self.is_set(flag) {
+            self.flags.push(flag);
+        }
+    }
+
+    pub fn count(&self, counter: StoryCounter) -> u32 {
+        self.counters.iter()
+            .find(|(c, _)| *c == counter)
+            .map_or(0, |(_, n)| *n)
+    }
+
+    pub fn increment(&mut self, counter: StoryCounter) {
+        match self.counters.iter_mut().find(|(c, _)| *c == counter) {
+            Some((_, n)) => *n += 1,
+            None => self.counters.push((counter, 1)),
+        }
+    }
+
+    /// Whether every one of `conditions` holds.
+    pub fn check(&self, conditions: &[StoryCondition]) -> bool {
+        conditions.iter().all(|condition| match *condition {
+            StoryCondition::Set(flag) => self.is_set(flag),
+            StoryCondition::NotSet(flag) => !self.is_set(flag),
+            StoryCondition::AtLeast(counter, n) => self.count(counter) >= n,
+            StoryCondition::Below(counter, n) => self.count(counter) < n,
+        })
+    }
+}
+
+/// Sent once a frame for each flag or counter that changed during it, including when a save is loaded.
+#[derive(Clone, Copy, Debug)]
+pub enum StoryChanged {
+    Set(StoryFlag),
+    Cleared(StoryFlag),
+    Counted(StoryCounter, u32),
+}
+
+/// Compares the flags against how they were last frame, so nothing that changes them
+/// (the inspector included) has to remember to send events.
+fn send_story_events(
+    flags: Res<StoryFlags>,
+    mut last: Local<StoryFlags>,
+    mut events: EventWriter<StoryChanged>,
+) {
+    if !flags.is_changed() || *flags == *last {
+        return;
+    }
+    for &flag in flags.flags.iter().filter(|&&flag| !last.is_set(flag)) {
+        events.send(StoryChanged::Set(flag));
+    }
+    for &flag in last.flags.iter().filter(|&&flag| !flags.is_set(flag)) {
+        events.send(StoryChanged::Cleared(flag));
+    }
+    let counters = flags.counters.iter().chain(last.counters.iter()).map(|(counter, _)| *counter);
+    let mut sent = vec![];
+    for counter in counters {
+        if !sent.contains(&counter) && flags.count(counter) != last.count(counter) {
+            events.send(StoryChanged::Counted(counter, flags.count(counter)));
+            sent.push(counter);
+        }
+    }
+    *last = flags.clone();
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use crate::init_systems::level::StoryConditional;
+    use crate::test_app::TestApp;
+    use super::{StoryFlag, StoryFlags};
+
+    fn shown_story_props(app: &mut TestApp) -> Vec<Entity> {
+        app.0.world.query_filtered::<(Entity, &Visibility), With<StoryConditional>>()
+            .iter(&app.0.world)
+            .filter(|(_, visibility)| visibility.is_visible)
+            .map(|(entity, _)| entity)
+            .collect()
+    }
+
+    #[test]
+    fn the_trash_can_tips_over_once_mousey_is_freed() {
+        let mut app = TestApp::new();
+        app.start_game(None);
+        let upright = shown_story_props(&mut app);
+        assert_eq!(upright.len(), 1);
+
+        app.0.world.resource_mut::<StoryFlags>().set(StoryFlag::MouseyFreed);
+        // The change is noticed at the end of one frame and acted on in the next.
+        app.step();
+        app.step();
+        let tipped = shown_story_props(&mut app);
+        assert_eq!(tipped.len(), 1);
+        assert_ne!(upright, tipped);
+    }
+}
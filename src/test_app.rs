@@ -0,0 +1,150 @@
+//! A headless `App` running the gameplay systems, for tests. No window, renderer or GPU is needed:
+//! sprites are empty handles, levels are read straight from `assets/levels`, and every frame
+//! takes exactly `FRAME_TIME`.
+
+use std::time::Duration;
+use bevy::ecs::query::ReadOnlyWorldQuery;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use bevy_rapier2d::prelude::{NoUserData, RapierPhysicsPlugin};
+use crate::GameplayPlugins;
+use crate::animations::AnimPlugin;
+use crate::assets::{AppState, GameAssets};
+use crate::init_systems::{EnvironmentInitPlugin, LevelState};
+use crate::gamepad_input::ActiveDevice;
+use crate::interaction::Interacting;
+use crate::keyboard_input::{InputMap, PlayerInput};
+use crate::player::Player;
+use crate::replay::FIXED_STEP;
+use crate::save::{Character, LoadedSave, SaveData};
+use crate::story::{StoryFlag, StoryFlags, StoryPlugin};
+use crate::transition::{LevelTransition, TransitionPlugin};
+
+pub const FRAME_TIME: Duration = FIXED_STEP;
+
+pub struct TestApp(pub App);
+
+impl TestApp {
+    /// An app sitting in the main menu, ready for `start_game`.
+    pub fn new() -> Self {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin::default())
+            .add_plugin(TransformPlugin)
+            .add_plugin(HierarchyPlugin)
+            .add_plugin(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(100.))
+            .add_state(AppState::MainMenu)
+            .init_resource::<Audio>()
+            .init_resource::<Windows>()
+            .init_resource::<PlayerInput>()
+            .init_resource::<InputMap>()
+            .init_resource::<ActiveDevice>()
+            .add_plugin(TransitionPlugin)
+            .add_plugin(StoryPlugin)
+            .add_plugin(AnimPlugin)
+            .add_plugin(EnvironmentInitPlugin)
+            .add_plugins(GameplayPlugins);
+
+        let assets = GameAssets::stub(&mut app.world);
+        app.insert_resource(assets);
+
+        let mut test_app = TestApp(app);
+        test_app.step();
+        test_app
+    }
+
+    /// Starts a new game, or continues `save` if there is one.
+    pub fn start_game(&mut self, save: Option<LoadedSave>) {
+        if let Some(save) = save {
+            let level = save.0.level;
+            // As loading the save from the menu would.
+            *self.0.world.resource_mut::<StoryFlags>() = save.0.flags.clone();
+            self.0.world.insert_resource(save);
+            if *self.level() != level {
+                self.0.world.resource_mut::<State<LevelState>>().set(level).unwrap();
+                self.step();
+            }
+        }
+        self.0.world.resource_mut::<State<AppState>>().set(AppState::InGame).unwrap();
+        self.step();
+    }
+
+    /// Continues a game saved with `character` at `position` in `level`.
+    pub fn start_as(&mut self, level: LevelState, character: Character, position: (f32, f32), flags: StoryFlags) {
+        self.start_game(Some(saved_as(level, character, position, flags)));
+    }
+
+    pub fn step(&mut self) {
+        let time = self.0.world.resource::<Time>();
+        let last_update = time.last_update().unwrap_or_else(|| time.startup());
+        self.0.insert_resource(TimeUpdateStrategy::ManualInstant(last_update + FRAME_TIME));
+        self.0.update();
+    }
+
+    /// Steps until `done` holds, failing the test if it doesn't within `max_frames`.
+    pub fn step_until(&mut self, max_frames: usize, what: &str, mut done: impl FnMut(&mut World) -> bool) {
+        for _ in 0..max_frames {
+            if done(&mut self.0.world) {
+                return;
+            }
+            self.step();
+        }
+        assert!(done(&mut self.0.world), "{what} didn't happen within {max_frames} frames");
+    }
+
+    /// Holds the interact button for a single frame.
+    pub fn press_interact(&mut self) {
+        self.input().just_interacted = true;
+        self.step();
+        self.input().just_interacted = false;
+    }
+
+    /// Has `traveller` use the door they're standing at, and waits for the screen to fade back in on
+    /// them in `target`.
+    pub fn go_through_door(&mut self, traveller: Entity, target: LevelState) {
+        self.step_until(30, "reaching the door", |world| world.get::<Interacting>(traveller).is_some());
+        self.press_interact();
+        self.step_until(240, "going through the door", |world| {
+            *world.resource::<State<LevelState>>().current() == target
+                && world.get::<Player>(traveller).is_some()
+                && !world.resource::<LevelTransition>().is_running()
+        });
+    }
+
+    pub fn input(&mut self) -> Mut<PlayerInput> {
+        self.0.world.resource_mut::<PlayerInput>()
+    }
+
+    pub fn level(&self) -> &LevelState {
+        self.0.world.resource::<State<LevelState>>().current()
+    }
+
+    /// The only entity matching the query filter `F`.
+    pub fn single<F: ReadOnlyWorldQuery>(&mut self) -> Entity {
+        self.0.world.query_filtered::<Entity, F>().single(&self.0.world)
+    }
+
+    pub fn has<T: Component>(&self, entity: Entity) -> bool {
+        self.0.world.get::<T>(entity).is_some()
+    }
+}
+
+/// A save made with `character` at `position` in `level`, and nobody else moved yet.
+pub fn saved_as(level: LevelState, character: Character, position: (f32, f32), flags: StoryFlags) -> LoadedSave {
+    LoadedSave(SaveData {
+        saved_at: 0,
+        level,
+        character,
+        position,
+        flags,
+        places: default(),
+        settings: default(),
+    })
+}
+
+/// The story once Trunk has knocked the trash can over.
+pub fn mousey_freed() -> StoryFlags {
+    let mut flags = StoryFlags::default();
+    flags.set(StoryFlag::MouseyFreed);
+    flags
+}
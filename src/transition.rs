@@ -0,0 +1,231 @@
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+use crate::assets::AppState;
+use crate::click_to_move::feet;
+use crate::companion::{Companion, START_DISTANCE};
+use crate::init_systems::environment::Door;
+use crate::init_systems::level::{LevelEntity, SpawnPoints};
+use crate::init_systems::{LevelState, YOffset};
+use crate::interaction::Interacting;
+use crate::roster::{ControllableCharacter, unlocked};
+use crate::story::StoryFlags;
+
+const FADE_TIME: f32 = 0.4;
+
+pub struct TransitionPlugin;
+
+impl Plugin for TransitionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LevelTransition>()
+            .add_event::<DoorArrival>()
+            .add_startup_system(spawn_fade_overlay)
+            .add_system(run_level_transition)
+            .add_system_set(
+                SystemSet::on_exit(AppState::InGame)
+                    .with_system(cancel_level_transition)
+            );
+    }
+}
+
+/// Sent once whoever went through a door has been placed at the spawn point on the other side,
+/// with where that spawn point is. Mousey is moved off it again at once, to hop out of the door.
+pub struct DoorArrival(pub Entity, pub Vec2);
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+enum TransitionStep {
+    #[default]
+    Idle,
+    FadeOut(f32),
+    Switch,
+    Arrive,
+    FadeIn(f32),
+}
+
+/// Fades the screen out, moves a character through a door into its target level, and fades back in.
+/// The companions following them come along.
+#[derive(Resource, Default)]
+pub struct LevelTransition {
+    step: TransitionStep,
+    pending: Option<(Entity, Door)>,
+    companions: Vec<Entity>,
+}
+
+impl LevelTransition {
+    pub fn begin(&mut self, traveller: Entity, door: Door) {
+        self.pending = Some((traveller, door));
+        self.step = TransitionStep::FadeOut(0.);
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.step != TransitionStep::Idle
+    }
+}
+
+#[derive(Component)]
+pub struct FadeOverlay;
+
+fn spawn_fade_overlay(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                ..default()
+            },
+            background_color: Color::NONE.into(),
+            focus_policy: FocusPolicy::Pass,
+            z_index: ZIndex::Global(100),
+            ..default()
+        },
+        FadeOverlay,
+    ));
+}
+
+fn run_level_transition(
+    mut commands: Commands,
+    mut transition: ResMut<LevelTransition>,
+    mut level: ResMut<State<LevelState>>,
+    spawn_points: Res<SpawnPoints>,
+    flags: Res<StoryFlags>,
+    time: Res<Time>,
+    mut travellers: Query<&mut Transform>,
+    offsets: Query<&YOffset>,
+    mut companions: Query<(Entity, &ControllableCharacter, &LevelEntity, &mut Companion)>,
+    mut overlay: Query<&mut BackgroundColor, With<FadeOverlay>>,
+    mut arrivals: EventWriter<DoorArrival>,
+) {
+    let alpha = match transition.step {
+        TransitionStep::Idle => return,
+        TransitionStep::FadeOut(t) => {
+            let t = t + time.delta_seconds() / FADE_TIME;
+            if t < 1. {
+                transition.step = TransitionStep::FadeOut(t);
+            } else {
+                // Hand the traveller and whoever is following them over to the target level a frame
+                // before switching, so the old level's teardown doesn't take them along.
+                if let Some((traveller, door)) = transition.pending.clone() {
+                    commands.entity(traveller)
+                        .insert(LevelEntity(door.target))
+                        .remove::<Interacting>();
+                    // Only those following, or waiting close by: anyone else was left behind on purpose.
+                    let from = travellers.get(traveller).ok().map(|trans| feet(trans, offsets.get(traveller).ok()));
+                    let nearby = |entity: Entity| {
+                        let pos = travellers.get(entity).ok().map(|trans| feet(trans, offsets.get(entity).ok()));
+                        matches!((from, pos), (Some(from), Some(pos)) if from.distance(pos) < START_DISTANCE)
+                    };
+                    transition.companions = companions.iter()
+                        .filter(|(entity, character, owner, companion)| {
+                            *entity != traveller
+                                && owner.0 == *level.current()
+                                && unlocked(character.0, &flags)
+                                && (companion.is_following() || nearby(*entity))
+                        })
+                        .map(|(entity, ..)| entity)
+                        .collect();
+                    for &companion in transition.companions.iter() {
+                        commands.entity(companion).insert(LevelEntity(door.target));
+                    }
+                }
+                transition.step = TransitionStep::Switch;
+            }
+            t.min(1.)
+        }
+        TransitionStep::Switch => {
+            if let Some((_, door)) = &transition.pending {
+                // If another level change got queued first this frame, try again next frame.
+                if *level.current() != door.target && level.set(door.target).is_err() {
+                    return;
+                }
+            }
+            transition.step = TransitionStep::Arrive;
+            1.
+        }
+        TransitionStep::Arrive => {
+            // Spawn points are only filled in once the target level has been entered.
+            if let Some((_, door)) = &transition.pending {
+                if *level.current() != door.target {
+                    return;
+                }
+            }
+            if let Some((traveller, door)) = transition.pending.take() {
+                let pos = spawn_points.get(&door.spawn_point);
+                for entity in std::iter::once(traveller).chain(transition.companions.drain(..)) {
+                    if let Ok(mut trans) = travellers.get_mut(entity) {
+                        trans.translation.x = pos.x;
+                        trans.translation.y = pos.y;
+                    }
+                    // Whatever path they were on was through the old level.
+                    if let Ok((.., mut companion)) = companions.get_mut(entity) {
+                        companion.replan();
+                    }
+                }
+                arrivals.send(DoorArrival(traveller, pos));
+            }
+            transition.step = TransitionStep::FadeIn(0.);
+            1.
+        }
+        TransitionStep::FadeIn(t) => {
+            let t = t + time.delta_seconds() / FADE_TIME;
+            transition.step = if t < 1. { TransitionStep::FadeIn(t) } else { TransitionStep::Idle };
+            1. - t.min(1.)
+        }
+    };
+
+    for mut color in overlay.iter_mut() {
+        color.0.set_a(alpha);
+    }
+}
+
+fn cancel_level_transition(
+    mut transition: ResMut<LevelTransition>,
+    mut overlay: Query<&mut BackgroundColor, With<FadeOverlay>>,
+) {
+    *transition = LevelTransition::default();
+    for mut color in overlay.iter_mut() {
+        color.0 = Color::NONE;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use crate::init_systems::{LevelState, Mousey};
+    use crate::init_systems::level::LevelEntity;
+    use crate::interaction::Interacting;
+    use crate::player::{Player, Trunk};
+    use crate::save::Character;
+    use crate::test_app::{mousey_freed, TestApp};
+
+    #[test]
+    fn mousey_goes_through_the_front_door() {
+        let mut app = TestApp::new();
+        // On the front door's spawn point, which is in front of the door.
+        app.start_as(LevelState::HouseFront, Character::Mousey, (360., -140.), mousey_freed());
+        let mousey = app.single::<(With<Mousey>, With<Player>)>();
+
+        app.step_until(30, "Mousey reaching the door", |world| world.get::<Interacting>(mousey).is_some());
+        app.press_interact();
+
+        app.step_until(120, "the level switching", |world| {
+            *world.resource::<State<LevelState>>().current() == LevelState::HouseInside
+        });
+        app.step_until(120, "Mousey landing inside", |world| world.get::<Player>(mousey).is_some());
+        assert_eq!(app.0.world.query_filtered::<Entity, With<Mousey>>().iter(&app.0.world).count(), 1);
+    }
+
+    #[test]
+    fn whoever_stays_behind_is_still_there_after_a_round_trip() {
+        let mut app = TestApp::new();
+        app.start_as(LevelState::HouseFront, Character::Trunk, (360., -140.), default());
+        let trunk = app.single::<With<Trunk>>();
+
+        app.go_through_door(trunk, LevelState::HouseInside);
+        assert_eq!(app.0.world.query_filtered::<Entity, With<Mousey>>().iter(&app.0.world).count(), 0);
+
+        // Back out the door Trunk came in through.
+        app.go_through_door(trunk, LevelState::HouseFront);
+        assert_eq!(app.single::<With<Trunk>>(), trunk);
+        let mousey = app.single::<With<Mousey>>();
+        assert_eq!(app.0.world.get::<LevelEntity>(mousey).unwrap().0, LevelState::HouseFront);
+    }
+}
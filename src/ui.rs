@@ -0,0 +1,17 @@
+use bevy::prelude::*;
+
+/// A node covering the whole window that stacks its children vertically in the centre.
+pub fn full_screen_column(background: Color) -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+            position_type: PositionType::Absolute,
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        background_color: background.into(),
+        ..default()
+    }
+}
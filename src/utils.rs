@@ -1,24 +1,58 @@
 use std::f32::consts::PI;
-use crate::init_systems::{AutoSizeOnY, AutoSortOnY, YOffset, TRUNK_SCALE, Mousey, LevelState};
+use crate::init_systems::{AutoSizeOnY, AutoSortOnY, YOffset, TRUNK_SCALE, Mousey};
 use bevy::hierarchy::{BuildChildren, Children};
 use bevy::math::{Vec2, Vec3};
-use bevy::prelude::{default, Commands, Entity, Query, Transform, TransformBundle, With, Without, Component, EventReader, Parent, Res, ResMut, State};
+use bevy::prelude::{default, App, Bundle, Commands, Entity, IntoSystemDescriptor, Plugin, Query, SystemSet, Transform, TransformBundle, With, Without, Component, EventReader, Parent, Res, ResMut};
 use bevy::time::Time;
 use bevy_rapier2d::dynamics::{LockedAxes, RigidBody};
 use bevy_rapier2d::geometry::Collider;
-use bevy_rapier2d::prelude::{ActiveEvents, Damping, Sensor, Velocity};
-use crate::animations::{Animations, Animator, AnimEnum};
+use bevy_rapier2d::prelude::{Damping, Sensor, Velocity};
+use crate::animations::{Animations, Animator, AnimEnum, Clip, Locomotion, play_locomotion};
 use crate::animations::AnimEnum::TrunkAttack;
-use crate::assets::{SpriteEnum};
-use crate::init_systems::environment::DoorInter;
-use crate::init_systems::LevelState::HouseInside;
-use crate::keyboard_input::PlayerInput;
-use crate::player::{DEADZONE, Flippable, InteractEvent, Player, PlayerInteractor, TRUNK_FRICTION};
+use crate::assets::{AppState, SpriteEnum};
+use crate::camera::CameraTarget;
+use crate::init_systems::environment::Door;
+use crate::interaction::{FreeMousey, UseDoor};
+use crate::player::{Flippable, Player, TRUNK_FRICTION};
+use crate::schedule::GameSystems;
+use crate::story::{StoryFlag, StoryFlags};
+use crate::transition::{DoorArrival, LevelTransition};
 
 pub const Y_SCALE_FACTOR: f32 = 0.001;
 
-#[derive(Component, Clone, Copy, Debug)]
-pub struct Interactable;
+/// Scripted animations: knocking over the trash can, and Mousey hopping through doors.
+pub struct CutscenePlugin;
+
+impl Plugin for CutscenePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(AppState::InGame)
+                .label(GameSystems::Animation)
+                .after(GameSystems::Interaction)
+                .with_system(attack_system.before(play_locomotion))
+                .with_system(mouse_trash_animator)
+                .with_system(mouse_door_anim_player)
+                .with_system(door_arrival)
+                .with_system(mouse_door_anim_finish)
+                .with_system(mouse_idle_anim)
+        );
+    }
+}
+
+/// Scales and sorts sprites by how far up the screen they are, once everything has moved.
+pub struct DepthPlugin;
+
+impl Plugin for DepthPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(AppState::InGame)
+                .label(GameSystems::Camera)
+                .after(GameSystems::Animation)
+                .with_system(update_size_on_y)
+                .with_system(auto_sort_on_y)
+        );
+    }
+}
 
 pub fn update_size_on_y(mut query: Query<&mut Transform, With<AutoSizeOnY>>) {
     for mut trans in query.iter_mut() {
@@ -70,20 +104,21 @@ pub struct MouseTrashAnimated(pub f32);
 #[derive(Component)]
 pub struct TrunkAttacking(pub Entity);
 
-pub fn mousey_interact(
-    mut ev: EventReader<InteractEvent>,
-    mut q: Query<&Parent, (With<Mousey>, With<Interactable>)>,
-    mut player: Query<(Entity, &mut Animator), With<Player>>,
+pub fn free_mousey(
+    mut ev: EventReader<FreeMousey>,
+    q: Query<&Parent>,
+    mut player: Query<&mut Animator, With<Player>>,
     mut commands: Commands,
     anims: Res<Animations>
 ) {
     for ev in ev.iter() {
-        let Ok(parent) = q.get(ev.interactable) else { return; };
-        for (player, mut animator) in player.iter_mut() {
-            commands.entity(player).remove::<Player>()
-                .insert(TrunkAttacking(parent.get()));
-            animator.play_anim(anims.get(AnimEnum::TrunkAttack));
-        }
+        let Ok(parent) = q.get(ev.interactable) else { continue; };
+        let Ok(mut animator) = player.get_mut(ev.trunk) else { continue; };
+        commands.entity(ev.trunk).remove::<Player>()
+            .insert(TrunkAttacking(parent.get()));
+        // The can only needs knocking over once.
+        commands.entity(ev.interactable).despawn();
+        animator.play_anim(anims.get(AnimEnum::TrunkAttack));
     }
 }
 
@@ -93,8 +128,8 @@ pub fn attack_system(
 ) {
     for (ent, mut anim, trunk) in q.iter_mut() {
         if !anim.playing && anim.current_anim.anim_enum == TrunkAttack {
-            commands.entity(ent).remove::<TrunkAttacking>();
-            commands.entity(trunk.0).insert(MouseTrashAnimated(0.));
+            commands.entity(ent).remove::<TrunkAttacking>().remove::<CameraTarget>();
+            commands.entity(trunk.0).insert((MouseTrashAnimated(0.), CameraTarget));
         }
     }
 }
@@ -108,6 +143,7 @@ pub fn mouse_trash_animator(
     mut q: Query<(&mut MouseTrashAnimated, &mut Transform, Entity, Option<&BeginAnimPos>)>,
     time: Res<Time>,
     assets: Res<Animations>,
+    mut flags: ResMut<StoryFlags>,
     mut commands: Commands,
 ) {
     for (mut mouse, mut trans, entity, begin) in q.iter_mut() {
@@ -122,40 +158,31 @@ pub fn mouse_trash_animator(
             commands.entity(entity).remove::<MouseTrashAnimated>()
                 .remove::<BeginAnimPos>()
                 .insert(Player)
-                .insert((
-                    YOffset(0.),
-                    WalkingMouse,
-                    PlayerInteractor,
-                    Animator::new(assets.map.get(&AnimEnum::MouseyWalk).unwrap().clone()),
-                    Flippable { right_facing: false },
-                    Velocity::default(),
-                    LockedAxes::ROTATION_LOCKED,
-                    Damping {
-                        linear_damping: TRUNK_FRICTION,
-                        ..default()
-                    },
-                    RigidBody::Dynamic,
-                    Collider::ball(100.),
-                    ActiveEvents::COLLISION_EVENTS
-                ));
+                .insert(walking_mousey_bundle(&assets));
+            flags.set(StoryFlag::MouseyFreed);
             continue;
         }
         mouse.0 += time.delta_seconds();
     }
 }
 
-pub fn mouse_walk_anim(
-    mut q: Query<&mut Animator, With<WalkingMouse>>,
-    input: Res<PlayerInput>,
-    anims: Res<Animations>,
-) {
-    for mut anim in q.iter_mut() {
-        if input.movement.length() < DEADZONE {
-            anim.play_sprite(SpriteEnum::MouseyWalk1);
-        } else {
-            anim.play_anim(anims.get(AnimEnum::MouseyWalk));
-        }
-    }
+/// Everything Mousey needs to walk around once out of the trash can.
+pub fn walking_mousey_bundle(anims: &Animations) -> impl Bundle {
+    (
+        YOffset(0.),
+        WalkingMouse,
+        Animator::new(anims.get(AnimEnum::MouseyWalk)),
+        Locomotion::new(Clip::Sprite(SpriteEnum::MouseyWalk1), Clip::Anim(AnimEnum::MouseyWalk)),
+        Flippable { right_facing: false },
+        Velocity::default(),
+        LockedAxes::ROTATION_LOCKED,
+        Damping {
+            linear_damping: TRUNK_FRICTION,
+            ..default()
+        },
+        RigidBody::Dynamic,
+        Collider::ball(100.),
+    )
 }
 
 pub fn mouse_idle_anim(
@@ -172,30 +199,38 @@ pub const MOUSE_DOOR_HOP_TIME: f32 = 0.5;
 #[derive(Component)]
 pub struct MouseDoorHopAnimated(pub f32);
 
-pub fn door_interact(
-    mut ev: EventReader<InteractEvent>,
-    q: Query<Entity, (With<DoorInter>, With<Interactable>)>,
-    players: Query<Entity, (With<Player>, With<Mousey>)>,
+/// The door Mousey is hopping into.
+#[derive(Component)]
+pub struct UsingDoor(pub Door);
+
+pub fn use_door(
+    mut ev: EventReader<UseDoor>,
+    players: Query<Option<&Mousey>, With<Player>>,
+    mut transition: ResMut<LevelTransition>,
     mut commands: Commands,
 ) {
-    for ev in ev.iter() {
-        println!("{:?}", ev);
-        let Ok(_) = q.get(ev.interactable) else { return; };
-        let Ok(player) = players.get(ev.interactor) else { return; };
-        commands.entity(player).remove::<Player>()
-            .insert(Sensor)
-            .insert(YOffset(-200.))
-            .insert(MouseDoorHopAnimated(0.));
+    for UseDoor { user, door } in ev.iter() {
+        let Ok(mousey) = players.get(*user) else { continue; };
+        if transition.is_running() {
+            continue;
+        }
+        let mut player = commands.entity(*user);
+        player.remove::<Player>();
+        if mousey.is_some() {
+            player.insert((Sensor, YOffset(-200.), MouseDoorHopAnimated(0.), UsingDoor(door.clone())));
+        } else {
+            transition.begin(*user, door.clone());
+        }
     }
 }
 
 pub fn mouse_door_anim_player(
-    mut q: Query<(&mut MouseDoorHopAnimated, &mut Transform, Entity, Option<&BeginAnimPos>)>,
+    mut q: Query<(&mut MouseDoorHopAnimated, &mut Transform, Entity, &UsingDoor, Option<&BeginAnimPos>)>,
     time: Res<Time>,
     mut commands: Commands,
-    mut state: ResMut<State<LevelState>>
+    mut transition: ResMut<LevelTransition>,
 ) {
-    for (mut mouse, mut trans, entity, begin) in q.iter_mut() {
+    for (mut mouse, mut trans, entity, door, begin) in q.iter_mut() {
         let Some(begin) = begin else {
             commands.entity(entity).insert(BeginAnimPos(trans.translation.truncate()));
             continue;
@@ -206,15 +241,31 @@ pub fn mouse_door_anim_player(
         if mouse.0 > MOUSE_DOOR_HOP_TIME {
             commands.entity(entity).remove::<MouseDoorHopAnimated>()
                 .remove::<BeginAnimPos>()
-                .insert(Player)
-                .remove::<Sensor>();
-            state.set(HouseInside).unwrap();
+                .remove::<UsingDoor>();
+            transition.begin(entity, door.0.clone());
             continue;
         }
         mouse.0 += time.delta_seconds();
     }
 }
 
+pub fn door_arrival(
+    mut arrivals: EventReader<DoorArrival>,
+    mut q: Query<(&mut Transform, Option<&Mousey>)>,
+    mut commands: Commands,
+) {
+    for DoorArrival(entity, _) in arrivals.iter() {
+        let Ok((mut trans, mousey)) = q.get_mut(*entity) else { continue; };
+        if mousey.is_some() {
+            // Start the hop out of the door 40 left of and 200 above the spawn point, so it lands on it.
+            trans.translation += Vec3::new(-40., 200., 0.);
+            commands.entity(*entity).insert((YOffset(0.), MouseDoorHopFinishAnim(0.)));
+        } else {
+            commands.entity(*entity).insert(Player);
+        }
+    }
+}
+
 #[derive(Component)]
 pub struct MouseDoorHopFinishAnim(pub f32);
 
@@ -248,4 +299,38 @@ fn mouse_trash_interp(x: f32) -> f32 {
 
 fn mouse_door_hop_interp(x: f32) -> f32 {
     (PI * x).sin()
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::With;
+    use crate::init_systems::Mousey;
+    use crate::interaction::Interacting;
+    use crate::player::{Player, Trunk};
+    use crate::story::{StoryFlag, StoryFlags};
+    use crate::test_app::TestApp;
+    use super::*;
+
+    #[test]
+    fn trunk_frees_mousey_from_the_trash_can() {
+        let mut app = TestApp::new();
+        app.start_game(None);
+        let trunk = app.single::<With<Trunk>>();
+        let mousey = app.single::<With<Mousey>>();
+
+        // Walk Trunk up to the trash can.
+        let mousey_pos = app.0.world.get::<Transform>(mousey).unwrap().translation;
+        app.0.world.get_mut::<Transform>(trunk).unwrap().translation = mousey_pos + Vec3::new(-40., 0., 0.);
+        app.step_until(30, "Trunk reaching Mousey", |world| world.get::<Interacting>(trunk).is_some());
+
+        app.press_interact();
+        assert!(app.has::<TrunkAttacking>(trunk));
+        assert!(!app.has::<Player>(trunk));
+
+        app.step_until(120, "the attack knocking the can over", |world| {
+            world.get::<MouseTrashAnimated>(mousey).is_some()
+        });
+        app.step_until(120, "Mousey climbing out", |world| world.get::<Player>(mousey).is_some());
+        assert!(app.has::<WalkingMouse>(mousey));
+        assert!(app.0.world.resource::<StoryFlags>().is_set(StoryFlag::MouseyFreed));
+    }
+}